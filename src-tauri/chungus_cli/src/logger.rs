@@ -0,0 +1,11 @@
+use chungus_ops::logging::ClientSideLogger;
+
+/// Forwards the progress messages the GUI would show in its loading overlay to tracing so they
+/// end up on stderr (with `RUST_LOG=info`) instead of polluting the analysis on stdout.
+pub struct TracingLogger;
+
+impl ClientSideLogger for TracingLogger {
+  fn message(&self, message: impl AsRef<str>) {
+    tracing::info!("{}", message.as_ref());
+  }
+}
@@ -0,0 +1,369 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use structopt::StructOpt;
+use tracing::subscriber::set_global_default;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{EnvFilter, Registry};
+
+use chungus_ops::analysis::{Analysis, Budget};
+use chungus_ops::diagnostics::Diagnostics;
+use chungus_ops::error::CoreError;
+use chungus_ops::module::Location;
+use chungus_ops::persistent_cache::{build_dependency_cache_persistent, PersistentCache};
+use chungus_ops::resolve::Resolver;
+use chungus_ops::webpack_resolve::WebpackResolveConfig;
+use chungus_ops::DependencyCache;
+
+use crate::logger::TracingLogger;
+
+mod logger;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+  name = "chungus",
+  about = "Headless dependency analysis for javascript projects"
+)]
+enum Command {
+  /// Build the analysis for an entrypoint and write it out as json
+  Analyse(AnalyseOptions),
+  /// List the import cycles reachable from an entrypoint as json
+  Cycles(CyclesOptions),
+  /// Compare two analyses written by `analyse`, for example from main and from a branch
+  Diff(DiffOptions),
+  /// Check an entrypoint against a budget file, exits with 1 when anything is over
+  Budget(BudgetOptions),
+  /// List the imports that didn't resolve and the files that couldn't be read
+  Diagnostics(DiagnosticsOptions),
+}
+
+#[derive(Debug, StructOpt)]
+struct ProjectOptions {
+  /// Root of the project, imports that are not relative are resolved from here
+  #[structopt(long, parse(from_os_str))]
+  root: PathBuf,
+  /// Directory (relative to the root) that is also searched for modules. Can be repeated
+  #[structopt(long = "include", parse(from_os_str), number_of_values = 1)]
+  included_directories: Vec<PathBuf>,
+  /// Extension that is treated as a javascript module. Can be repeated, defaults to jsx, js, ts
+  /// and tsx
+  #[structopt(long = "extension", number_of_values = 1)]
+  extensions: Vec<String>,
+  /// tsconfig.json used for files without a tsconfig.json or jsconfig.json of their own
+  #[structopt(long, parse(from_os_str))]
+  tsconfig: Option<PathBuf>,
+  /// Condition matched against package.json exports and imports. Can be repeated, defaults to
+  /// webpack, browser and module
+  #[structopt(long = "condition", number_of_values = 1)]
+  conditions: Vec<String>,
+  /// JSON dump of webpack's resolve configuration, for its alias and modules
+  #[structopt(long, parse(from_os_str))]
+  webpack_resolve: Option<PathBuf>,
+  /// Read and parse files on this many threads, 0 for one per cpu. Files are read one at a time
+  /// when not given
+  #[structopt(long)]
+  threads: Option<usize>,
+  /// Keep parsed files in this file between runs, only the files that changed are parsed again
+  #[structopt(long, parse(from_os_str), conflicts_with = "threads")]
+  cache_file: Option<PathBuf>,
+}
+
+impl ProjectOptions {
+  fn create_resolver(&self) -> Result<Resolver, CoreError> {
+    let mut resolver =
+      chungus_ops::start_resolve_project(&self.root, self.included_directories.clone())?;
+
+    if !self.extensions.is_empty() {
+      resolver.extensions = self
+        .extensions
+        .iter()
+        .map(|extension| extension.trim_start_matches('.').to_string())
+        .collect();
+    }
+    if !self.conditions.is_empty() {
+      resolver.conditions = self.conditions.clone();
+    }
+    resolver.tsconfig = self.tsconfig.clone();
+
+    if let Some(path) = &self.webpack_resolve {
+      let context = path.parent().unwrap_or_else(|| Path::new("."));
+      WebpackResolveConfig::from_reader(BufReader::new(File::open(path)?), context)?
+        .apply(&mut resolver);
+    }
+
+    Ok(resolver)
+  }
+}
+
+#[derive(Debug, StructOpt)]
+struct AnalysisOptions {
+  #[structopt(flatten)]
+  project: ProjectOptions,
+  /// The file the dependency tree starts from. Can be repeated, every module is then tagged with
+  /// the entrypoints that reach it
+  #[structopt(
+    long = "entrypoint",
+    parse(from_os_str),
+    number_of_values = 1,
+    required = true
+  )]
+  entrypoints: Vec<PathBuf>,
+  /// A webpack stats file, rollup bundle metadata or an esbuild metafile, used to assign chunks
+  /// and mark tree shaken modules
+  #[structopt(long, parse(from_os_str))]
+  stats: Option<PathBuf>,
+  /// Which of each entrypoint's chunks to use when augmenting with the stats file
+  #[structopt(long, default_value = "0")]
+  entrypoint_chunk: usize,
+  /// The directory the build was written to. When given, gzip and brotli sizes are measured on
+  /// the emitted file of every chunk in the stats file
+  #[structopt(long, parse(from_os_str))]
+  build_dir: Option<PathBuf>,
+  /// Estimate gzip and brotli sizes of every module by compressing its source file, before
+  /// loaders and minification
+  #[structopt(long)]
+  compressed_source: bool,
+}
+
+#[derive(Debug, StructOpt)]
+struct AnalyseOptions {
+  #[structopt(flatten)]
+  analysis: AnalysisOptions,
+  #[structopt(flatten)]
+  output: OutputOptions,
+}
+
+#[derive(Debug, StructOpt)]
+struct BudgetOptions {
+  #[structopt(flatten)]
+  analysis: AnalysisOptions,
+  /// The budget file, toml when it ends in .toml and json otherwise
+  #[structopt(long, parse(from_os_str))]
+  budget: PathBuf,
+  /// Write the violations as json instead of text
+  #[structopt(long)]
+  json: bool,
+  #[structopt(flatten)]
+  output: OutputOptions,
+}
+
+#[derive(Debug, StructOpt)]
+struct CyclesOptions {
+  #[structopt(flatten)]
+  project: ProjectOptions,
+  /// The file the dependency tree starts from
+  #[structopt(long, parse(from_os_str))]
+  entrypoint: PathBuf,
+  #[structopt(flatten)]
+  output: OutputOptions,
+}
+
+#[derive(Debug, StructOpt)]
+struct DiagnosticsOptions {
+  #[structopt(flatten)]
+  project: ProjectOptions,
+  /// The file the dependency tree starts from. Can be repeated
+  #[structopt(
+    long = "entrypoint",
+    parse(from_os_str),
+    number_of_values = 1,
+    required = true
+  )]
+  entrypoints: Vec<PathBuf>,
+  /// Write the diagnostics as json instead of text
+  #[structopt(long)]
+  json: bool,
+  #[structopt(flatten)]
+  output: OutputOptions,
+}
+
+#[derive(Debug, StructOpt)]
+struct DiffOptions {
+  /// The analysis before the change
+  #[structopt(parse(from_os_str))]
+  before: PathBuf,
+  /// The analysis after the change
+  #[structopt(parse(from_os_str))]
+  after: PathBuf,
+  /// Write the differences as json instead of text
+  #[structopt(long)]
+  json: bool,
+  #[structopt(flatten)]
+  output: OutputOptions,
+}
+
+#[derive(Debug, StructOpt)]
+struct OutputOptions {
+  /// Write to this file instead of stdout
+  #[structopt(long, short, parse(from_os_str))]
+  output: Option<PathBuf>,
+  /// Pretty print the json output
+  #[structopt(long)]
+  pretty: bool,
+}
+
+fn create_analysis(
+  project: &ProjectOptions,
+  entrypoints: &[PathBuf],
+) -> Result<(Resolver, Analysis), CoreError> {
+  let resolver = project.create_resolver()?;
+  let entrypoints = entrypoints
+    .iter()
+    .map(Location::new)
+    .collect::<Result<Vec<_>, _>>()?;
+  let logger = TracingLogger;
+
+  let mut persistent = project
+    .cache_file
+    .as_ref()
+    .map(|path| PersistentCache::load(path, &resolver));
+  let mut cache = DependencyCache::new();
+  let mut diagnostics = Diagnostics::default();
+  for entrypoint in entrypoints.iter() {
+    let found = match (&mut persistent, project.threads) {
+      (Some(persistent), _) => {
+        build_dependency_cache_persistent(&resolver, entrypoint, &mut cache, persistent, &logger)?
+      }
+      (None, Some(threads)) => chungus_ops::parallel_cache::build_dependency_cache_parallel(
+        &resolver, entrypoint, &mut cache, threads, &logger,
+      )?,
+      (None, None) => {
+        chungus_ops::build_dependency_cache(&resolver, entrypoint, &mut cache, &logger)?
+      }
+    };
+    diagnostics.merge(found);
+  }
+  if let Some(persistent) = &persistent {
+    persistent.save()?;
+  }
+
+  let mut analysis = Analysis::create_from_entrypoints(&resolver, &cache, &entrypoints, &logger)?;
+  analysis.add_diagnostics(diagnostics);
+  Ok((resolver, analysis))
+}
+
+impl AnalysisOptions {
+  fn create_analysis(&self) -> Result<Analysis, CoreError> {
+    let (resolver, mut analysis) = create_analysis(&self.project, &self.entrypoints)?;
+    if self.compressed_source {
+      analysis.add_compressed_source_sizes();
+    }
+
+    if let Some(stats) = &self.stats {
+      let file = OpenOptions::new().read(true).open(stats)?;
+      let mut report =
+        chungus_ops::webpack_report::create_report_from_reader(&resolver, BufReader::new(file))?;
+      if let Some(build_dir) = &self.build_dir {
+        report.add_compressed_sizes(build_dir);
+      }
+      analysis.augment_with_webpack_report(&report, self.entrypoint_chunk);
+    }
+
+    Ok(analysis)
+  }
+}
+
+fn analyse(options: AnalyseOptions) -> Result<(), CoreError> {
+  let analysis = options.analysis.create_analysis()?;
+  write_json(&analysis, &options.output)
+}
+
+fn check_budget(options: BudgetOptions) -> Result<(), CoreError> {
+  let budget = Budget::from_path(&options.budget)?;
+  let violations = options.analysis.create_analysis()?.check_budget(&budget);
+
+  if options.json {
+    write_json(&violations, &options.output)?;
+  } else {
+    let mut writer = create_writer(&options.output)?;
+    for violation in violations.iter() {
+      writeln!(writer, "{}", violation)?;
+    }
+    writer.flush()?;
+  }
+
+  if violations.is_empty() {
+    Ok(())
+  } else {
+    Err(CoreError::custom(&format!(
+      "{} budget violations",
+      violations.len()
+    )))
+  }
+}
+
+fn cycles(options: CyclesOptions) -> Result<(), CoreError> {
+  let (_, analysis) = create_analysis(&options.project, std::slice::from_ref(&options.entrypoint))?;
+  write_json(&analysis.cycles(), &options.output)
+}
+
+fn diagnostics(options: DiagnosticsOptions) -> Result<(), CoreError> {
+  let (_, analysis) = create_analysis(&options.project, &options.entrypoints)?;
+
+  if options.json {
+    return write_json(analysis.diagnostics(), &options.output);
+  }
+  let mut writer = create_writer(&options.output)?;
+  write!(writer, "{}", analysis.diagnostics())?;
+  writer.flush()?;
+  Ok(())
+}
+
+fn diff(options: DiffOptions) -> Result<(), CoreError> {
+  let read_analysis = |path: &Path| -> Result<Analysis, CoreError> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    Ok(serde_json::from_reader(BufReader::new(file))?)
+  };
+  let diff = read_analysis(&options.before)?.diff(&read_analysis(&options.after)?);
+
+  if options.json {
+    return write_json(&diff, &options.output);
+  }
+  let mut writer = create_writer(&options.output)?;
+  write!(writer, "{}", diff)?;
+  writer.flush()?;
+  Ok(())
+}
+
+fn create_writer(options: &OutputOptions) -> Result<Box<dyn Write>, CoreError> {
+  Ok(match &options.output {
+    Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+    None => Box::new(BufWriter::new(std::io::stdout())),
+  })
+}
+
+fn write_json(value: &impl Serialize, options: &OutputOptions) -> Result<(), CoreError> {
+  let mut writer = create_writer(options)?;
+
+  if options.pretty {
+    serde_json::to_writer_pretty(&mut writer, value)?;
+  } else {
+    serde_json::to_writer(&mut writer, value)?;
+  }
+  writeln!(writer)?;
+  writer.flush()?;
+
+  Ok(())
+}
+
+fn main() {
+  let registry = Registry::default()
+    .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")))
+    .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr));
+  let _ = set_global_default(registry);
+
+  let result = match Command::from_args() {
+    Command::Analyse(options) => analyse(options),
+    Command::Cycles(options) => cycles(options),
+    Command::Diff(options) => diff(options),
+    Command::Budget(options) => check_budget(options),
+    Command::Diagnostics(options) => diagnostics(options),
+  };
+
+  if let Err(error) = result {
+    eprintln!("chungus: {}", error);
+    std::process::exit(1);
+  }
+}
@@ -0,0 +1,84 @@
+use chungus_ops::parser::{ScanMode, UnresolvedImport};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+// Real world inputs that ship with the sample project. The polyfill bundles are big browserify
+// builds full of `require` calls, the minified versions are one very long line.
+const INPUTS: [(&str, &str); 4] = [
+  (
+    "polyfill.js",
+    include_str!("../src/sample_javascript/node_modules/babel-polyfill/dist/polyfill.js"),
+  ),
+  (
+    "polyfill.min.js",
+    include_str!("../src/sample_javascript/node_modules/babel-polyfill/dist/polyfill.min.js"),
+  ),
+  (
+    "core.js",
+    include_str!("../src/sample_javascript/node_modules/core-js/client/core.js"),
+  ),
+  (
+    "core.min.js",
+    include_str!("../src/sample_javascript/node_modules/core-js/client/core.min.js"),
+  ),
+];
+
+// There is no typescript in the sample project, so build a large tsx module out of a component
+// that uses the syntax the scanner has to get right: type imports, generics, jsx and templates.
+fn typescript_module(components: usize) -> String {
+  let mut module = String::new();
+  for index in 0..components {
+    module.push_str(&format!(
+      r#"
+import React, {{ useState }} from "react";
+import type {{ Props{index} }} from "./types/{index}";
+import {{ type Theme, styled }} from "@app/theme";
+export {{ helper{index} }} from "../helpers/{index}";
+
+const Lazy{index} = React.lazy(() => import("./lazy/{index}"));
+
+export function Component{index}<T extends object>({{ items }}: Props{index}<T>) {{
+  const [open, setOpen] = useState<boolean>(false);
+  const label = `item ${{items.length / 2}} of ${{require("./count/{index}").total}}`;
+  // require("./not-a-dependency")
+  return (
+    <div className="component" onClick={{() => setOpen(!open)}}>
+      {{open && <Lazy{index} label={{label}} />}}
+      Use import("./not-a-dependency-either") to load
+    </div>
+  );
+}}
+"#,
+      index = index
+    ));
+  }
+  module
+}
+
+fn parse_many(c: &mut Criterion) {
+  let typescript = typescript_module(500);
+  let inputs = INPUTS
+    .iter()
+    .map(|(name, contents)| (*name, *contents))
+    .chain(std::iter::once(("component.tsx", typescript.as_str())));
+
+  let mut group = c.benchmark_group("parse_many");
+  for (name, contents) in inputs {
+    group.throughput(Throughput::Bytes(contents.len() as u64));
+    for (mode_name, mode) in [
+      ("lexical", ScanMode::default()),
+      ("permissive", ScanMode::Permissive),
+    ]
+    .iter()
+    {
+      group.bench_with_input(
+        BenchmarkId::new(*mode_name, name),
+        contents,
+        |b, contents| b.iter(|| UnresolvedImport::parse_many_with_mode(contents, *mode).unwrap()),
+      );
+    }
+  }
+  group.finish();
+}
+
+criterion_group!(benches, parse_many);
+criterion_main!(benches);
@@ -1,10 +1,12 @@
-use crate::module::{Location, Module, ModuleKind, RelativePath};
+use crate::module::{DependencyKind, Location, ModuleKind, RelativePath};
 use crate::resolve::Resolver;
 use crate::DependencyCache;
 use parking_lot::RwLock;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use crate::compression::compressed_file_sizes;
+use crate::diagnostics::Diagnostics;
 use crate::error::CoreError;
 use crate::file::{find_highest_path, FileTree};
 use crate::logging::ClientSideLogger;
@@ -14,6 +16,22 @@ use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 
+pub use budget::{Budget, BudgetTarget, BudgetViolation, Limit, Measure, NamedLimit, PathLimit};
+pub use cycles::Cycle;
+pub use diff::{AnalysisDiff, ChunkChange, GroupChange, ImportEdge, ModuleSize, SizeChange};
+pub use dominators::RetainedSize;
+pub use packages::{DuplicatePackage, PackageCopy};
+pub use paths::ImportStep;
+pub use update::AnalysisDelta;
+
+mod budget;
+mod cycles;
+mod diff;
+mod dominators;
+mod packages;
+mod paths;
+mod update;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Analysis {
   node_map: HashMap<Location, usize>,
@@ -26,6 +44,83 @@ pub struct Analysis {
   all_nodes: Vec<Arc<RwLock<AnalysisNode>>>,
   entrypoint: Arc<RwLock<AnalysisNode>>,
   chunks: HashMap<usize, Chunk>,
+  // how the first node imported the second, the first import wins when there are several
+  #[serde(default, with = "edge_kinds")]
+  edge_kinds: HashMap<(usize, usize), DependencyKind>,
+  #[serde(default)]
+  cycles: Vec<Cycle>,
+  // indices of every entrypoint, the first one is `entrypoint`. Empty in analyses written before
+  // there could be several, use `entrypoint_indices`
+  #[serde(default)]
+  entrypoints: Vec<usize>,
+  #[serde(default)]
+  diagnostics: Diagnostics,
+  // the resolver's, so packages can be told apart once the analysis has been written out
+  #[serde(default = "default_module_directories")]
+  module_directories: Vec<String>,
+}
+
+fn default_module_directories() -> Vec<String> {
+  vec!["node_modules".to_string()]
+}
+
+/// Bytes taken up by a module, or by the modules in a group
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sizes {
+  /// The source file on disk
+  pub source: usize,
+  /// In the emitted bundle, rollup, vite and esbuild metadata have it
+  #[serde(default)]
+  pub parsed: Option<usize>,
+  /// What webpack's stats count for the module, after loaders ran but before minification
+  #[serde(default)]
+  pub stat: Option<usize>,
+  /// The source file compressed, once `add_compressed_source_sizes` has run. This is before
+  /// loaders and minification, so it's only a stand in for compressed bundle bytes
+  #[serde(default)]
+  pub gzip: Option<usize>,
+  #[serde(default)]
+  pub brotli: Option<usize>,
+}
+
+impl Sizes {
+  fn from_disk(location: &Location) -> Self {
+    let source = std::fs::metadata(location)
+      .ok()
+      .filter(|metadata| metadata.is_file())
+      .map(|metadata| metadata.len() as usize)
+      .unwrap_or_default();
+
+    Self {
+      source,
+      ..Default::default()
+    }
+  }
+
+  /// The closest to the bytes in the bundle there is, parsed bytes, then webpack's stat size and
+  /// then the source
+  pub fn bundled(&self) -> usize {
+    self.parsed.or(self.stat).unwrap_or(self.source)
+  }
+}
+
+impl std::ops::Add for Sizes {
+  type Output = Sizes;
+
+  fn add(self, other: Self) -> Self::Output {
+    let add_optional = |a: Option<usize>, b: Option<usize>| match (a, b) {
+      (Some(a), Some(b)) => Some(a + b),
+      (a, b) => a.or(b),
+    };
+
+    Sizes {
+      source: self.source + other.source,
+      parsed: add_optional(self.parsed, other.parsed),
+      stat: add_optional(self.stat, other.stat),
+      gzip: add_optional(self.gzip, other.gzip),
+      brotli: add_optional(self.brotli, other.brotli),
+    }
+  }
 }
 
 #[derive(Debug, Serialize, Deserialize, Eq, Clone)]
@@ -43,6 +138,17 @@ struct AnalysisNode {
   resolver_relative_path: RelativePath,
   incoming: HashSet<usize>,
   outgoing: HashSet<usize>,
+  // index into the analysis' cycles when this module imports itself through other modules
+  cycle: Option<usize>,
+  // indices of the entrypoints this module can be reached from
+  #[serde(default)]
+  entrypoints: Vec<usize>,
+  // For groups this is the modules directly in the directory and everything under it. For
+  // modules both are the module itself.
+  #[serde(default)]
+  self_size: Sizes,
+  #[serde(default)]
+  inclusive_size: Sizes,
 }
 
 impl Hash for AnalysisNode {
@@ -114,6 +220,22 @@ impl Analysis {
     entrypoint: &Location,
     logger: &impl ClientSideLogger,
   ) -> Result<Self, CoreError> {
+    Self::create_from_entrypoints(resolver, cache, std::slice::from_ref(entrypoint), logger)
+  }
+
+  /// One analysis over several entrypoints, like the pages of an app, that all come out of the
+  /// same cache. Every module is tagged with the entrypoints it can be reached from. The first
+  /// entrypoint is the one chunks and import chains are worked out from.
+  #[tracing::instrument(skip(resolver, cache, logger))]
+  pub fn create_from_entrypoints(
+    resolver: &Resolver,
+    cache: &DependencyCache,
+    entrypoints: &[Location],
+    logger: &impl ClientSideLogger,
+  ) -> Result<Self, CoreError> {
+    let (entrypoint, other_entrypoints) = entrypoints
+      .split_first()
+      .ok_or_else(|| CoreError::custom("An analysis needs at least one entrypoint"))?;
     tracing::info!("Creating analysis at entry: {:?}", &entrypoint);
     // do some wild iteraton
     let resolver_relative_path = entrypoint.make_relative_to(&resolver.resolve_root)?;
@@ -130,6 +252,10 @@ impl Analysis {
       full_path: entrypoint.clone(),
       incoming: Default::default(),
       outgoing: Default::default(),
+      self_size: Sizes::from_disk(entrypoint),
+      inclusive_size: Sizes::from_disk(entrypoint),
+      cycle: None,
+      entrypoints: vec![],
     }));
 
     let (initial_analysis_groups, initial_analysis_group_map) = {
@@ -158,6 +284,10 @@ impl Analysis {
           full_path,
           incoming: Default::default(),
           outgoing: Default::default(),
+          self_size: Default::default(),
+          inclusive_size: Default::default(),
+          cycle: None,
+          entrypoints: vec![],
         }));
 
         groups.push(analysis_node);
@@ -175,6 +305,11 @@ impl Analysis {
 
       all_nodes: vec![root_node.clone()],
       entrypoint: root_node.clone(),
+      edge_kinds: Default::default(),
+      cycles: vec![],
+      entrypoints: vec![0],
+      diagnostics: Default::default(),
+      module_directories: resolver.module_directories.clone(),
       node_map: {
         let mut map = HashMap::new();
         map.insert(entrypoint.clone(), 0);
@@ -182,29 +317,29 @@ impl Analysis {
       },
     };
 
-    analysis.populate(resolver, cache, &*logger)?;
-
-    {
-      let highest_path = find_highest_path(
-        analysis
-          .analysis_groups
-          .iter()
-          .map(|g| g.read().full_path.clone()),
-      );
-      if let Some(highest_path) = highest_path {
-        use std::iter::FromIterator;
-        let filter = HashSet::from_iter(
-          analysis
-            .all_nodes
-            .iter()
-            .map(|node| node.read().full_path.clone()),
-        );
-        logger.message("Creating analysis navigation tree");
-        let tree = FileTree::open_from_root_path(&resolver, highest_path.as_ref(), &Some(filter))?;
-
-        analysis.file_tree = Some(tree);
-      };
+    analysis.populate(resolver, cache, 0, &*logger)?;
+    for other_entrypoint in other_entrypoints.iter() {
+      match analysis.node_map.get(other_entrypoint).copied() {
+        // already reached from one of the entrypoints before it
+        Some(index) => {
+          if !analysis.entrypoints.contains(&index) {
+            analysis.entrypoints.push(index);
+          }
+        }
+        None => {
+          analysis.insert_node(resolver, other_entrypoint.clone(), false, None)?;
+          let index = analysis.all_nodes.len() - 1;
+          analysis.entrypoints.push(index);
+          analysis.populate(resolver, cache, index, &*logger)?;
+        }
+      }
     }
+    analysis.tag_entrypoints();
+    analysis.find_cycles();
+    analysis.aggregate_sizes();
+    analysis.find_unresolved_imports(cache);
+
+    analysis.create_file_tree(resolver, logger)?;
 
     tracing::info!("Created analysis at entry: {:?}", &entrypoint);
     Ok(analysis)
@@ -221,32 +356,38 @@ impl Analysis {
       &self.entrypoint.read().full_path
     );
 
-    let chunks_in_entrypoint = webpack_report
-      .chunk_mapping
-      .get(&self.entrypoint.read().full_path)
-      .unwrap();
-
-    let chunk = chunks_in_entrypoint
-      .get(entrypoint_chunk_preference)
-      .unwrap();
-
-    tracing::info!(
-      "Entrypoint chunk index {} found {}",
-      entrypoint_chunk_preference,
-      chunk.id
-    );
+    let mut entrypoint_chunk_children = HashSet::new();
+    for entrypoint in self.entrypoint_indices() {
+      let entrypoint = self.all_nodes[entrypoint].read().full_path.clone();
+      let chunk = match webpack_report
+        .chunk_mapping
+        .get(&entrypoint)
+        .and_then(|chunks| chunks.get(entrypoint_chunk_preference))
+      {
+        Some(chunk) => chunk,
+        None => {
+          tracing::warn!(
+            "Entrypoint {:?} has no chunk {} in the report",
+            &entrypoint,
+            entrypoint_chunk_preference
+          );
+          continue;
+        }
+      };
 
-    use std::iter::FromIterator;
-    let entrypoint_chunk_children = {
-      let mut set = HashSet::new();
+      tracing::info!(
+        "Entrypoint {:?} chunk index {} found {}",
+        &entrypoint,
+        entrypoint_chunk_preference,
+        chunk.id
+      );
       for chunk in chunk.children.iter().chain(chunk.siblings.iter()) {
-        set.insert(*chunk);
+        entrypoint_chunk_children.insert(*chunk);
       }
 
       // also include ourselves if we're in the same chunk
-      set.insert(chunk.id);
-      set
-    };
+      entrypoint_chunk_children.insert(chunk.id);
+    }
 
     tracing::info!("Entrypoint children found {:?}", &entrypoint_chunk_children);
     let mut extra_nodes = vec![];
@@ -315,6 +456,7 @@ impl Analysis {
     }
 
     self.analysis_groups.extend(extra_nodes.into_iter());
+    self.index_groups();
 
     self.chunks = {
       let mut map = HashMap::new();
@@ -324,6 +466,201 @@ impl Analysis {
         }
       }
       map
+    };
+
+    for node in self.all_nodes.iter() {
+      let mut node = node.write();
+      let (parsed, stat) = if node.tree_shaken {
+        (None, None)
+      } else {
+        (
+          webpack_report.module_sizes.get(&node.full_path).copied(),
+          webpack_report
+            .module_stat_sizes
+            .get(&node.full_path)
+            .copied(),
+        )
+      };
+      node.self_size.parsed = parsed;
+      node.self_size.stat = stat;
+      node.inclusive_size = node.self_size;
+    }
+    self.aggregate_sizes();
+  }
+
+  /// Compresses the source file of every module, for budgets that are written in compressed bytes.
+  /// Stats files don't have the emitted bytes of a module, so it's the source that is measured and
+  /// not the built module. Chunk sizes come from `WebpackReport::add_compressed_sizes`, those are
+  /// measured on the emitted files.
+  #[tracing::instrument(skip(self))]
+  pub fn add_compressed_source_sizes(&mut self) {
+    for node in self.all_nodes.iter() {
+      let mut node = node.write();
+      match compressed_file_sizes(&node.full_path) {
+        Ok(sizes) => {
+          node.self_size.gzip = Some(sizes.gzip);
+          node.self_size.brotli = Some(sizes.brotli);
+        }
+        Err(e) => tracing::warn!("Could not compress {:?}: {}", &node.full_path, e),
+      }
+      node.inclusive_size = node.self_size;
+    }
+    self.aggregate_sizes();
+  }
+
+  // The navigation tree, with only the folders and files that are in the graph
+  fn create_file_tree(
+    &mut self,
+    resolver: &Resolver,
+    logger: &impl ClientSideLogger,
+  ) -> Result<(), CoreError> {
+    let highest_path = find_highest_path(
+      self
+        .analysis_groups
+        .iter()
+        .map(|g| g.read().full_path.clone()),
+    );
+    if let Some(highest_path) = highest_path {
+      let filter = self
+        .all_nodes
+        .iter()
+        .map(|node| node.read().full_path.clone())
+        .collect();
+      logger.message("Creating analysis navigation tree");
+      let tree = FileTree::open_from_root_path(&resolver, highest_path.as_ref(), &Some(filter))?;
+
+      self.file_tree = Some(tree);
+    };
+    Ok(())
+  }
+
+  // The outgoing edges of every node by index, sorted so that walks over the graph don't depend
+  // on hash set order
+  fn adjacency(&self) -> Vec<Vec<usize>> {
+    self
+      .all_nodes
+      .iter()
+      .map(|node| {
+        let mut outgoing: Vec<usize> = node.read().outgoing.iter().copied().collect();
+        outgoing.sort_unstable();
+        outgoing
+      })
+      .collect()
+  }
+
+  // Groups that have been split by chunk only count the modules in their own chunk
+  fn aggregate_sizes(&self) {
+    for group in self.analysis_groups.iter() {
+      let (chunk, immediate_children, inclusions) = {
+        let group = group.read();
+        (
+          group.chunk,
+          group.immediate_children.clone(),
+          group.inclusions.clone(),
+        )
+      };
+      let sum = |indices: &[usize]| {
+        indices
+          .iter()
+          .filter_map(|index| self.all_nodes.get(*index))
+          .map(|node| node.read())
+          .filter(|node| chunk.is_none() || node.chunk == chunk)
+          .fold(Sizes::default(), |total, node| total + node.self_size)
+      };
+
+      let self_size = sum(&immediate_children);
+      let inclusive_size = sum(&inclusions);
+      let mut group = group.write();
+      group.self_size = self_size;
+      group.inclusive_size = inclusive_size;
+    }
+  }
+
+  /// Imports in the graph that didn't resolve, and whatever went wrong while building the cache
+  /// once it has been added with `add_diagnostics`
+  pub fn diagnostics(&self) -> &Diagnostics {
+    &self.diagnostics
+  }
+
+  /// Adds what a build of the dependency cache reported
+  pub fn add_diagnostics(&mut self, diagnostics: Diagnostics) {
+    self.diagnostics.merge(diagnostics);
+  }
+
+  // The unresolved imports of every module in the graph, whichever build loaded it
+  fn find_unresolved_imports(&mut self, cache: &DependencyCache) {
+    let mut diagnostics = Diagnostics::default();
+    for node in self.all_nodes.iter() {
+      if let Some(module) = cache.get(&node.read().full_path) {
+        diagnostics.add_unresolved_imports(module);
+      }
+    }
+    self.diagnostics.unresolved_imports = diagnostics.unresolved_imports;
+    self.diagnostics.sort();
+  }
+
+  pub fn entrypoints(&self) -> Vec<Location> {
+    self
+      .entrypoint_indices()
+      .into_iter()
+      .map(|index| self.all_nodes[index].read().full_path.clone())
+      .collect()
+  }
+
+  // `entrypoints`, or only `entrypoint` for analyses that were written before there could be more
+  pub(crate) fn entrypoint_indices(&self) -> Vec<usize> {
+    if self.entrypoints.is_empty() {
+      vec![self.entrypoint_index()]
+    } else {
+      self.entrypoints.clone()
+    }
+  }
+
+  /// The entrypoints `location` can be reached from, in the order they were given
+  pub fn entrypoints_reaching(&self, location: &Location) -> Option<Vec<Location>> {
+    let index = *self.node_map.get(location)?;
+    let node = self.all_nodes[index].read();
+    Some(
+      node
+        .entrypoints
+        .iter()
+        .map(|entrypoint| self.all_nodes[*entrypoint].read().full_path.clone())
+        .collect(),
+    )
+  }
+
+  // Marks every node and group with the entrypoints that reach it
+  fn tag_entrypoints(&self) {
+    let adjacency = self.adjacency();
+    let mut reached_by: Vec<Vec<usize>> = vec![vec![]; self.all_nodes.len()];
+    for entrypoint in self.entrypoint_indices() {
+      let mut visited = vec![false; self.all_nodes.len()];
+      let mut queue = vec![entrypoint];
+      visited[entrypoint] = true;
+      while let Some(node) = queue.pop() {
+        reached_by[node].push(entrypoint);
+        for next in adjacency[node].iter().copied() {
+          if !visited[next] {
+            visited[next] = true;
+            queue.push(next);
+          }
+        }
+      }
+    }
+
+    for group in self.analysis_groups.iter() {
+      let mut group = group.write();
+      let mut entrypoints: Vec<usize> = group
+        .inclusions
+        .iter()
+        .flat_map(|node| reached_by[*node].iter().copied())
+        .collect();
+      entrypoints.sort_unstable();
+      entrypoints.dedup();
+      group.entrypoints = entrypoints;
+    }
+    for (node, entrypoints) in self.all_nodes.iter().zip(reached_by) {
+      node.write().entrypoints = entrypoints;
     }
   }
 
@@ -332,9 +669,10 @@ impl Analysis {
     &mut self,
     resolver: &Resolver,
     cache: &DependencyCache,
+    start: usize,
     logger: &impl ClientSideLogger,
   ) -> Result<(), CoreError> {
-    let mut queue = vec![(self.entrypoint.clone(), 0usize)];
+    let mut queue = vec![(self.all_nodes[start].clone(), start)];
 
     while !queue.is_empty() {
       tracing::debug!("Populating analysis, {} items in queue", queue.len());
@@ -352,14 +690,14 @@ impl Analysis {
 
       let module = module.unwrap();
 
-      let dependencies: Vec<Location> = module
+      let dependencies: Vec<(Location, DependencyKind)> = module
         .dependencies
         .iter()
-        .filter_map(|dep| dep.location())
+        .filter_map(|dep| dep.location().map(|location| (location, dep.kind())))
         .collect();
 
       let mut outgoing = HashSet::new();
-      for dependency in dependencies {
+      for (dependency, kind) in dependencies {
         logger.message(format!("Processing {:?}", &dependency));
         tracing::trace!("Processing dependency at {:?}", &dependency);
         let is_node_module = cache
@@ -390,98 +728,23 @@ impl Analysis {
 
             // we also want to attach ourselves to every analysis group that contains the target node;
             for group_path in target_node.all_possible_group_paths(&resolver) {
-              let relative_path = group_path?;
-              // Invariant. If this node exists in the tree, all of it's groups must also exist.
-              let index = self
-                .analysis_group_map
-                .get(&(relative_path, target_node.chunk))
-                .unwrap();
-              let mut analysis_group = self.analysis_groups.get(*index).unwrap().write();
+              let index = self.group_index(&group_path?, target_node.chunk)?;
+              let mut analysis_group = self.analysis_groups[index].write();
               analysis_group.incoming.insert(own_index);
             }
           }
           outgoing.insert(index);
+          self.edge_kinds.entry((own_index, index)).or_insert(kind);
         } else {
           tracing::debug!("Creating new analysis node from {:?}", &module);
-          use std::iter::FromIterator;
-
-          let new_analysis_node = Arc::new(RwLock::new(AnalysisNode {
-            identifier: dependency.as_ref().to_string_lossy().to_string(),
-            immediate_children: vec![],
-            inclusions: vec![],
-            tree_shaken: false,
-            chunk: None,
-            is_node_module,
-            depth: dependency.as_ref().components().count(),
-            stem: Some(
-              dependency
-                .as_ref()
-                .components()
-                .rev()
-                .take(1)
-                .collect::<PathBuf>(),
-            ),
-            resolver_relative_path: dependency.make_relative_to(&resolver.resolve_root)?,
-            outgoing: Default::default(),
-            incoming: HashSet::from_iter(vec![own_index]),
-            full_path: dependency.clone(),
-          }));
-
-          for (index, group_address) in new_analysis_node
-            .read()
-            .all_possible_group_paths(&resolver)
-            .enumerate()
-          {
-            let relative_path = group_address?;
-            if let Some(existing_group) = self
-              .analysis_group_map
-              .get(&(relative_path.clone(), new_analysis_node.read().chunk))
-            {
-              let analysis_group = self.analysis_groups.get(*existing_group).unwrap();
-              // this happens before insertion so we don't do a -1 here;
-              analysis_group.write().inclusions.push(self.all_nodes.len());
-              if index == 0 {
-                analysis_group
-                  .write()
-                  .immediate_children
-                  .push(self.all_nodes.len())
-              }
-            } else {
-              let location = Location::new(resolver.resolve_root.as_ref().join(&*relative_path))?;
-              let analysis_node = AnalysisNode {
-                identifier: location.as_ref().to_string_lossy().to_string(),
-                stem: None,
-                inclusions: vec![self.all_nodes.len()],
-                immediate_children: if index == 0 {
-                  vec![self.all_nodes.len()]
-                } else {
-                  vec![]
-                },
-                depth: location.as_ref().components().count(),
-                full_path: location,
-                is_node_module: false,
-                tree_shaken: false,
-                chunk: None,
-                resolver_relative_path: relative_path.clone(),
-                incoming: HashSet::from_iter(vec![own_index]),
-                outgoing: Default::default(),
-              };
-
-              self
-                .analysis_groups
-                .push(Arc::new(RwLock::new(analysis_node)));
-              self.analysis_group_map.insert(
-                (relative_path.clone(), new_analysis_node.read().chunk),
-                self.analysis_groups.len() - 1,
-              );
-            }
-          }
-
-          self.all_nodes.push(new_analysis_node.clone());
-          self.node_map.insert(dependency, self.all_nodes.len() - 1);
+          let new_analysis_node =
+            self.insert_node(resolver, dependency, is_node_module, Some(own_index))?;
 
           queue.push((new_analysis_node.clone(), self.all_nodes.len() - 1));
           outgoing.insert(self.all_nodes.len() - 1);
+          self
+            .edge_kinds
+            .insert((own_index, self.all_nodes.len() - 1), kind);
         }
       }
 
@@ -502,15 +765,8 @@ impl Analysis {
       // update all of the associated analysis groups
       {
         for group_path in next.read().all_possible_group_paths(&resolver) {
-          let location = group_path?;
-          // this must exist
-          let index = self
-            .analysis_group_map
-            .get(&(location.clone(), next.read().chunk))
-            .unwrap();
-
-          // invariant
-          let mut analysis_group = self.analysis_groups.get(*index).unwrap().write();
+          let index = self.group_index(&group_path?, next.read().chunk)?;
+          let mut analysis_group = self.analysis_groups[index].write();
           for item in outgoing.iter() {
             analysis_group.outgoing.insert(*item);
           }
@@ -519,4 +775,940 @@ impl Analysis {
     }
     Ok(())
   }
+
+  // Keys every group by its path and chunk again, after groups have been split or removed
+  fn index_groups(&mut self) {
+    self.analysis_group_map = self
+      .analysis_groups
+      .iter()
+      .enumerate()
+      .map(|(index, group)| {
+        let group = group.read();
+        ((group.resolver_relative_path.clone(), group.chunk), index)
+      })
+      .collect();
+  }
+
+  // The group at `path` that holds the modules of `chunk`. Modules that were tree shaken have no
+  // chunk, they are counted with whichever group is at the path.
+  fn group_index(&self, path: &RelativePath, chunk: Option<usize>) -> Result<usize, CoreError> {
+    if let Some(index) = self.analysis_group_map.get(&(path.clone(), chunk)) {
+      return Ok(*index);
+    }
+    self
+      .analysis_groups
+      .iter()
+      .position(|group| group.read().resolver_relative_path == *path)
+      .ok_or_else(|| CoreError::custom(&format!("There is no group for {:?}", path)))
+  }
+
+  // Adds a node for a module that isn't in the graph yet, along with any groups it needs
+  fn insert_node(
+    &mut self,
+    resolver: &Resolver,
+    dependency: Location,
+    is_node_module: bool,
+    importer: Option<usize>,
+  ) -> Result<Arc<RwLock<AnalysisNode>>, CoreError> {
+    use std::iter::FromIterator;
+
+    let new_analysis_node = Arc::new(RwLock::new(AnalysisNode {
+      identifier: dependency.as_ref().to_string_lossy().to_string(),
+      immediate_children: vec![],
+      inclusions: vec![],
+      tree_shaken: false,
+      chunk: None,
+      is_node_module,
+      depth: dependency.as_ref().components().count(),
+      stem: Some(
+        dependency
+          .as_ref()
+          .components()
+          .rev()
+          .take(1)
+          .collect::<PathBuf>(),
+      ),
+      resolver_relative_path: dependency.make_relative_to(&resolver.resolve_root)?,
+      outgoing: Default::default(),
+      incoming: HashSet::from_iter(importer),
+      self_size: Sizes::from_disk(&dependency),
+      inclusive_size: Sizes::from_disk(&dependency),
+      cycle: None,
+      entrypoints: vec![],
+      full_path: dependency.clone(),
+    }));
+
+    for (index, group_address) in new_analysis_node
+      .read()
+      .all_possible_group_paths(&resolver)
+      .enumerate()
+    {
+      let relative_path = group_address?;
+      if let Some(existing_group) = self
+        .analysis_group_map
+        .get(&(relative_path.clone(), new_analysis_node.read().chunk))
+      {
+        let analysis_group = self.analysis_groups.get(*existing_group).unwrap();
+        // this happens before insertion so we don't do a -1 here;
+        analysis_group.write().inclusions.push(self.all_nodes.len());
+        if index == 0 {
+          analysis_group
+            .write()
+            .immediate_children
+            .push(self.all_nodes.len())
+        }
+      } else {
+        let location = Location::new(resolver.resolve_root.as_ref().join(&*relative_path))?;
+        let analysis_node = AnalysisNode {
+          identifier: location.as_ref().to_string_lossy().to_string(),
+          stem: None,
+          inclusions: vec![self.all_nodes.len()],
+          immediate_children: if index == 0 {
+            vec![self.all_nodes.len()]
+          } else {
+            vec![]
+          },
+          depth: location.as_ref().components().count(),
+          full_path: location,
+          is_node_module: false,
+          tree_shaken: false,
+          chunk: None,
+          resolver_relative_path: relative_path.clone(),
+          incoming: HashSet::from_iter(importer),
+          outgoing: Default::default(),
+          self_size: Default::default(),
+          inclusive_size: Default::default(),
+          cycle: None,
+          entrypoints: vec![],
+        };
+
+        self
+          .analysis_groups
+          .push(Arc::new(RwLock::new(analysis_node)));
+        self.analysis_group_map.insert(
+          (relative_path.clone(), new_analysis_node.read().chunk),
+          self.analysis_groups.len() - 1,
+        );
+      }
+    }
+
+    self.all_nodes.push(new_analysis_node.clone());
+    self.node_map.insert(dependency, self.all_nodes.len() - 1);
+
+    Ok(new_analysis_node)
+  }
+}
+
+// json maps only have string keys, so the edges are written as a list of triples
+mod edge_kinds {
+  use crate::module::DependencyKind;
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+  use std::collections::HashMap;
+
+  pub fn serialize<S: Serializer>(
+    edge_kinds: &HashMap<(usize, usize), DependencyKind>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    let mut edges: Vec<(usize, usize, DependencyKind)> = edge_kinds
+      .iter()
+      .map(|(&(from, to), &kind)| (from, to, kind))
+      .collect();
+    edges.sort_unstable_by_key(|&(from, to, _)| (from, to));
+    edges.serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<HashMap<(usize, usize), DependencyKind>, D::Error> {
+    let edges = Vec::<(usize, usize, DependencyKind)>::deserialize(deserializer)?;
+    Ok(
+      edges
+        .into_iter()
+        .map(|(from, to, kind)| ((from, to), kind))
+        .collect(),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::build_dependency_cache;
+  use crate::diagnostics::FileErrorKind;
+  use crate::logging::NoopLogger;
+  use std::path::Path;
+
+  fn sample_analysis() -> (Resolver, Analysis) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript");
+    let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    let entrypoint = Location::new(root.join("module/a.js")).unwrap();
+
+    let mut cache = DependencyCache::new();
+    build_dependency_cache(&resolver, &entrypoint, &mut cache, &NoopLogger).unwrap();
+    let analysis =
+      Analysis::create_from_cache(&resolver, &cache, &entrypoint, &NoopLogger).unwrap();
+    (resolver, analysis)
+  }
+
+  // A stats file where only module/a.js and module/b.js are left in the bundle
+  fn report_bundling_a_and_b(resolver: &Resolver) -> WebpackReport {
+    let location = |path: &str| Location::new(resolver.resolve_root.as_ref().join(path)).unwrap();
+    let chunk = Chunk {
+      id: 0,
+      name: "main".into(),
+      initial: true,
+      parents: vec![],
+      siblings: vec![],
+      children: vec![],
+      parsed_size: 30,
+      files: vec![],
+      gzip_size: None,
+      brotli_size: None,
+    };
+    WebpackReport {
+      chunk_mapping: vec![
+        (location("module/a.js"), vec![chunk.clone()]),
+        (location("module/b.js"), vec![chunk.clone()]),
+      ]
+      .into_iter()
+      .collect(),
+      chunk_id_map: vec![(0, chunk)].into_iter().collect(),
+      module_sizes: vec![(location("module/a.js"), 10), (location("module/b.js"), 20)]
+        .into_iter()
+        .collect(),
+      module_stat_sizes: Default::default(),
+    }
+  }
+
+  // What an analysis written before `fields` were added to its nodes looks like
+  fn remove_from_nodes(written: &mut serde_json::Value, fields: &[&str]) {
+    let remove = |node: &mut serde_json::Value| {
+      let node = node.as_object_mut().unwrap();
+      for field in fields.iter() {
+        node.remove(*field);
+      }
+    };
+    for key in ["all_nodes", "analysis_groups"].iter() {
+      written[*key]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .for_each(remove);
+    }
+    remove(&mut written["entrypoint"]);
+  }
+
+  fn group(analysis: &Analysis, resolver: &Resolver, path: &str) -> AnalysisNode {
+    let relative_path = RelativePath::make_from_path(path, &resolver.resolve_root).unwrap();
+    let index = analysis.analysis_group_map[&(relative_path, None)];
+    analysis.analysis_groups[index].read().clone()
+  }
+
+  #[test]
+  fn it_sums_sizes_into_groups() {
+    let (resolver, analysis) = sample_analysis();
+    let size_of = |path: &str| {
+      let path = resolver.resolve_root.as_ref().join(path);
+      std::fs::metadata(path).unwrap().len() as usize
+    };
+
+    let module = group(&analysis, &resolver, "module");
+    assert_eq!(
+      module.self_size.source,
+      size_of("module/a.js") + size_of("module/b.js")
+    );
+    assert_eq!(module.self_size, module.inclusive_size);
+    assert_eq!(module.self_size.parsed, None);
+
+    let node_modules = group(&analysis, &resolver, "node_modules");
+    assert_eq!(node_modules.self_size.source, 0);
+    assert!(node_modules.inclusive_size.source > 0);
+  }
+
+  #[test]
+  fn it_takes_parsed_sizes_from_a_report() {
+    let (resolver, mut analysis) = sample_analysis();
+    let location = |path: &str| Location::new(resolver.resolve_root.as_ref().join(path)).unwrap();
+
+    let chunk = Chunk {
+      id: 0,
+      name: "main".into(),
+      initial: true,
+      parents: vec![],
+      siblings: vec![],
+      children: vec![],
+      parsed_size: 30,
+      files: vec![],
+      gzip_size: None,
+      brotli_size: None,
+    };
+    let report = WebpackReport {
+      chunk_mapping: vec![
+        (location("module/a.js"), vec![chunk.clone()]),
+        (location("module/b.js"), vec![chunk.clone()]),
+      ]
+      .into_iter()
+      .collect(),
+      chunk_id_map: vec![(0, chunk)].into_iter().collect(),
+      module_sizes: vec![(location("module/a.js"), 10), (location("module/b.js"), 20)]
+        .into_iter()
+        .collect(),
+      module_stat_sizes: vec![(location("module/a.js"), 15)].into_iter().collect(),
+    };
+    analysis.augment_with_webpack_report(&report, 0);
+
+    let module = analysis
+      .analysis_groups
+      .iter()
+      .find(|group| group.read().full_path == location("module"))
+      .unwrap()
+      .read()
+      .clone();
+    assert_eq!(module.inclusive_size.parsed, Some(30));
+    assert_eq!(module.inclusive_size.stat, Some(15));
+  }
+
+  #[test]
+  fn it_loads_analyses_written_before_sizes() {
+    let (_, analysis) = sample_analysis();
+    let mut written = serde_json::to_value(&analysis).unwrap();
+    remove_from_nodes(&mut written, &["self_size", "inclusive_size"]);
+
+    let loaded: Analysis = serde_json::from_value(written).unwrap();
+    assert_eq!(loaded.all_nodes.len(), analysis.all_nodes.len());
+    assert_eq!(loaded.entrypoint.read().self_size, Sizes::default());
+    assert_eq!(
+      serde_json::from_str::<Sizes>(r#"{ "source": 10 }"#).unwrap(),
+      Sizes {
+        source: 10,
+        ..Default::default()
+      }
+    );
+  }
+
+  #[test]
+  fn it_explains_why_a_module_is_included() {
+    let (resolver, analysis) = sample_analysis();
+    let location = |path: &str| Location::new(resolver.resolve_root.as_ref().join(path)).unwrap();
+
+    assert_eq!(
+      analysis.paths_to(&location("c.js"), 3).unwrap(),
+      vec![vec![ImportStep {
+        importer: location("module/a.js"),
+        imported: location("c.js"),
+        kind: DependencyKind::Import,
+      }]]
+    );
+    assert_eq!(
+      analysis.paths_to(&location("module/a.js"), 1).unwrap(),
+      vec![vec![]]
+    );
+    assert!(analysis.paths_to(&location("package.json"), 1).is_err());
+
+    // the kinds are kept when the analysis is written out
+    let reloaded: Analysis =
+      serde_json::from_str(&serde_json::to_string(&analysis).unwrap()).unwrap();
+    assert!(!reloaded.edge_kinds.is_empty());
+    assert_eq!(reloaded.edge_kinds, analysis.edge_kinds);
+  }
+
+  #[test]
+  fn it_finds_what_each_module_retains() {
+    let (resolver, analysis) = sample_analysis();
+    let location = |path: &str| Location::new(resolver.resolve_root.as_ref().join(path)).unwrap();
+
+    let retained = analysis.retained_sizes();
+    let entrypoint = &retained[0];
+    assert_eq!(entrypoint.location, location("module/a.js"));
+    assert_eq!(entrypoint.immediate_dominator, None);
+    assert_eq!(entrypoint.retained_modules, analysis.all_nodes.len());
+    assert_eq!(
+      entrypoint.retained_size,
+      analysis
+        .all_nodes
+        .iter()
+        .fold(Sizes::default(), |sizes, node| sizes
+          + node.read().self_size)
+    );
+
+    let c = retained
+      .iter()
+      .find(|retained| retained.location == location("c.js"))
+      .unwrap();
+    assert_eq!(c.immediate_dominator, Some(location("module/a.js")));
+    assert_eq!(c.retained_modules, 1);
+
+    let largest = analysis.largest_retained(2);
+    assert_eq!(largest.len(), 2);
+    assert!(largest
+      .iter()
+      .all(|retained| retained.location != location("module/a.js")));
+    assert!(largest[0].retained_size.source >= largest[1].retained_size.source);
+  }
+
+  #[test]
+  fn it_finds_import_cycles() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_cycles");
+    let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    let location = |path: &str| Location::new(root.join(path)).unwrap();
+
+    let mut cache = DependencyCache::new();
+    build_dependency_cache(&resolver, location("index.js"), &mut cache, &NoopLogger).unwrap();
+    let analysis =
+      Analysis::create_from_cache(&resolver, &cache, &location("index.js"), &NoopLogger).unwrap();
+
+    assert_eq!(analysis.cycles().len(), 1);
+    let cycle = &analysis.cycles()[0];
+    let mut members = cycle.members.clone();
+    members.sort();
+    let mut expected = vec![
+      location("components/index.js"),
+      location("components/Button.js"),
+      location("components/Form.js"),
+    ];
+    expected.sort();
+    assert_eq!(members, expected);
+    assert_eq!(
+      cycle.closing_edges,
+      vec![ImportStep {
+        importer: location("components/Button.js"),
+        imported: location("components/index.js"),
+        kind: DependencyKind::Import,
+      }]
+    );
+
+    assert_eq!(
+      analysis.cycle_of(&location("components/Form.js")),
+      Some(cycle)
+    );
+    assert_eq!(analysis.cycle_of(&location("index.js")), None);
+
+    // analyses written before cycles were found still load
+    let mut written = serde_json::to_value(&analysis).unwrap();
+    written.as_object_mut().unwrap().remove("cycles");
+    let loaded: Analysis = serde_json::from_value(written).unwrap();
+    assert!(loaded.cycles().is_empty());
+  }
+
+  #[test]
+  fn it_finds_duplicate_packages() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_duplicates");
+    let mut resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    // widget only pulls in its own react-is through its package.json dependencies
+    resolver.recursively_resolve_node_modules = true;
+    let location = |path: &str| Location::new(root.join(path)).unwrap();
+
+    let mut cache = DependencyCache::new();
+    build_dependency_cache(&resolver, location("index.js"), &mut cache, &NoopLogger).unwrap();
+    let analysis =
+      Analysis::create_from_cache(&resolver, &cache, &location("index.js"), &NoopLogger).unwrap();
+
+    let duplicates = analysis.duplicate_packages(&resolver).unwrap();
+    assert_eq!(duplicates.len(), 1);
+    let react_is = &duplicates[0];
+    assert_eq!(react_is.name, "react-is");
+
+    let hoisted = &react_is.copies[0];
+    assert_eq!(hoisted.version.as_deref(), Some("17.0.2"));
+    assert_eq!(hoisted.directory, location("node_modules/react-is"));
+    assert_eq!(
+      hoisted.modules,
+      vec![location("node_modules/react-is/index.js")]
+    );
+    assert_eq!(hoisted.imported_by, vec![location("index.js")]);
+
+    let nested = &react_is.copies[1];
+    assert_eq!(nested.version.as_deref(), Some("16.13.1"));
+    assert_eq!(
+      nested.imported_by,
+      vec![location("node_modules/widget/index.js")]
+    );
+
+    // the nested copy is the larger one, so the hoisted one is counted as the waste
+    assert!(nested.size.source > hoisted.size.source);
+    assert_eq!(react_is.wasted_size, hoisted.size);
+  }
+
+  #[test]
+  fn it_diffs_two_analyses() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript");
+    let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    let analysis_of = |path: &str| {
+      let entrypoint = Location::new(root.join(path)).unwrap();
+      let mut cache = DependencyCache::new();
+      build_dependency_cache(&resolver, &entrypoint, &mut cache, &NoopLogger).unwrap();
+      let analysis =
+        Analysis::create_from_cache(&resolver, &cache, &entrypoint, &NoopLogger).unwrap();
+      // diffs are made from analyses that have been written out
+      serde_json::from_str::<Analysis>(&serde_json::to_string(&analysis).unwrap()).unwrap()
+    };
+    let relative = |path: &str| RelativePath::make_from_path(path, &resolver.resolve_root).unwrap();
+
+    let before = analysis_of("module/b.js");
+    let after = analysis_of("module/a.js");
+    assert!(after.diff(&after).added_modules.is_empty());
+
+    let diff = before.diff(&after);
+    let added: Vec<RelativePath> = diff
+      .added_modules
+      .iter()
+      .map(|module| module.path.clone())
+      .collect();
+    assert!(added.contains(&relative("module/a.js")));
+    assert!(added.contains(&relative("c.js")));
+    assert!(!added.contains(&relative("module/b.js")));
+    assert!(diff.removed_modules.is_empty());
+    assert_eq!(diff.added_packages, vec!["babel-polyfill".to_string()]);
+    assert!(diff.added_imports.contains(&ImportEdge {
+      importer: relative("module/a.js"),
+      imported: relative("c.js"),
+    }));
+    assert_eq!(
+      diff.total_size.delta(),
+      (diff.total_size.after.source - diff.total_size.before.source) as i64
+    );
+
+    let text = diff.to_string();
+    assert!(text.starts_with("module/a.js: "));
+    assert!(text.contains("Added packages: babel-polyfill"));
+    assert!(text.contains("  + c.js ("));
+  }
+
+  #[test]
+  fn it_diffs_packages_in_other_module_directories() {
+    let directory = tempfile::tempdir().unwrap();
+    let write = |path: &str, contents: &str| {
+      let path = directory.path().join(path);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, contents).unwrap()
+    };
+    write("before.js", "");
+    write("after.js", "import widget from 'widget';\n");
+    write(
+      "vendor/widget/package.json",
+      r#"{ "name": "widget", "main": "index.js" }"#,
+    );
+    write("vendor/widget/index.js", "");
+
+    let root = Location::new(directory.path()).unwrap();
+    let mut resolver = Resolver::new(&root, vec![]);
+    resolver.module_directories = vec!["vendor".to_string()];
+    let analysis_of = |path: &str| {
+      let entrypoint = Location::new(root.as_ref().join(path)).unwrap();
+      let mut cache = DependencyCache::new();
+      build_dependency_cache(&resolver, &entrypoint, &mut cache, &NoopLogger).unwrap();
+      let analysis =
+        Analysis::create_from_cache(&resolver, &cache, &entrypoint, &NoopLogger).unwrap();
+      serde_json::from_str::<Analysis>(&serde_json::to_string(&analysis).unwrap()).unwrap()
+    };
+
+    let diff = analysis_of("before.js").diff(&analysis_of("after.js"));
+    assert_eq!(diff.added_packages, vec!["widget".to_string()]);
+  }
+
+  #[test]
+  fn it_checks_a_budget() {
+    let (_, analysis) = sample_analysis();
+    let budget: Budget = serde_json::from_str(
+      r#"{
+        "entrypoints": [
+          { "path": "./module/a.js", "max_modules": 2 },
+          { "path": "module/b.js", "max_modules": 1 }
+        ],
+        "groups": [
+          { "path": "module", "max_bytes": 1, "measure": "source" },
+          { "path": "missing", "max_bytes": 1 }
+        ],
+        "packages": [{ "name": "babel-polyfill", "max_bytes": 100000, "max_modules": 10 }],
+        "chunks": [{ "name": "main", "max_bytes": 1 }]
+      }"#,
+    )
+    .unwrap();
+
+    let violations = analysis.check_budget(&budget);
+    assert_eq!(violations.len(), 2);
+    assert_eq!(
+      violations[0],
+      BudgetViolation {
+        target: BudgetTarget::Entrypoint(PathBuf::from("./module/a.js")),
+        measure: None,
+        limit: 2,
+        actual: analysis.all_nodes.len(),
+      }
+    );
+    assert_eq!(
+      violations[1].target,
+      BudgetTarget::Group(PathBuf::from("module"))
+    );
+    assert_eq!(violations[1].measure, Some(Measure::Source));
+    assert_eq!(
+      violations[1].to_string(),
+      format!(
+        "folder module is {} B source, over its budget of 1 B",
+        violations[1].actual
+      )
+    );
+  }
+
+  #[test]
+  fn it_leaves_tree_shaken_modules_out_of_budgets() {
+    let (resolver, mut analysis) = sample_analysis();
+    let budget: Budget = serde_json::from_str(
+      r#"{
+        "entrypoints": [{ "path": "module/a.js", "max_modules": 2 }],
+        "packages": [{ "name": "babel-polyfill", "max_modules": 0 }]
+      }"#,
+    )
+    .unwrap();
+    assert_eq!(analysis.check_budget(&budget).len(), 2);
+
+    analysis.augment_with_webpack_report(&report_bundling_a_and_b(&resolver), 0);
+    assert!(analysis.check_budget(&budget).is_empty());
+  }
+
+  #[test]
+  fn it_leaves_tree_shaken_modules_out_of_retained_sizes() {
+    let (resolver, mut analysis) = sample_analysis();
+    let location = |path: &str| Location::new(resolver.resolve_root.as_ref().join(path)).unwrap();
+    analysis.augment_with_webpack_report(&report_bundling_a_and_b(&resolver), 0);
+
+    let retained = analysis.retained_sizes();
+    let retained_by = |path: &str| {
+      retained
+        .iter()
+        .find(|retained| retained.location == location(path))
+        .unwrap()
+    };
+    assert_eq!(retained_by("module/a.js").retained_modules, 2);
+    assert_eq!(retained_by("module/a.js").retained_size.parsed, Some(30));
+    assert_eq!(retained_by("c.js").retained_modules, 0);
+    assert_eq!(retained_by("c.js").retained_size, Sizes::default());
+    assert_eq!(
+      analysis.largest_retained(1)[0].location,
+      location("module/b.js")
+    );
+  }
+
+  #[test]
+  fn it_attributes_modules_to_entrypoints() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript");
+    let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    let location = |path: &str| Location::new(root.join(path)).unwrap();
+    let entrypoints = vec![
+      location("module/b.js"),
+      location("module/a.js"),
+      location("c.js"),
+    ];
+
+    let mut cache = DependencyCache::new();
+    for entrypoint in entrypoints.iter() {
+      build_dependency_cache(&resolver, entrypoint, &mut cache, &NoopLogger).unwrap();
+    }
+    let analysis =
+      Analysis::create_from_entrypoints(&resolver, &cache, &entrypoints, &NoopLogger).unwrap();
+
+    assert_eq!(analysis.entrypoints(), entrypoints);
+    assert_eq!(
+      analysis.entrypoint.read().full_path,
+      location("module/b.js")
+    );
+    assert_eq!(
+      analysis.entrypoints_reaching(&location("module/b.js")),
+      Some(vec![location("module/b.js"), location("module/a.js")])
+    );
+    assert_eq!(
+      analysis.entrypoints_reaching(&location("c.js")),
+      Some(vec![location("module/a.js"), location("c.js")])
+    );
+    assert_eq!(
+      analysis.entrypoints_reaching(&location("module/a.js")),
+      Some(vec![location("module/a.js")])
+    );
+
+    // a group is reached by every entrypoint that reaches something in it
+    let module = group(&analysis, &resolver, "module");
+    assert_eq!(module.entrypoints.len(), 2);
+    assert!(Analysis::create_from_entrypoints(&resolver, &cache, &[], &NoopLogger).is_err());
+
+    // analyses written before there could be several entrypoints only have the one
+    let mut written = serde_json::to_value(&analysis).unwrap();
+    written.as_object_mut().unwrap().remove("entrypoints");
+    remove_from_nodes(
+      &mut written,
+      &["entrypoints", "self_size", "inclusive_size"],
+    );
+    let loaded: Analysis = serde_json::from_value(written).unwrap();
+    assert_eq!(loaded.entrypoints(), vec![location("module/b.js")]);
+    assert!(loaded.entrypoint.read().entrypoints.is_empty());
+  }
+
+  #[test]
+  fn it_finds_the_chunks_of_every_entrypoint() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript");
+    let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    let location = |path: &str| Location::new(root.join(path)).unwrap();
+    let entrypoints = vec![location("module/b.js"), location("c.js")];
+
+    let mut cache = DependencyCache::new();
+    for entrypoint in entrypoints.iter() {
+      build_dependency_cache(&resolver, entrypoint, &mut cache, &NoopLogger).unwrap();
+    }
+    let mut analysis =
+      Analysis::create_from_entrypoints(&resolver, &cache, &entrypoints, &NoopLogger).unwrap();
+
+    let chunk = |id: usize, name: &str| Chunk {
+      id,
+      name: name.into(),
+      initial: true,
+      parents: vec![],
+      siblings: vec![],
+      children: vec![],
+      parsed_size: 0,
+      files: vec![],
+      gzip_size: None,
+      brotli_size: None,
+    };
+    let report = WebpackReport {
+      chunk_mapping: vec![
+        (location("module/b.js"), vec![chunk(0, "b")]),
+        (location("c.js"), vec![chunk(1, "c")]),
+      ]
+      .into_iter()
+      .collect(),
+      chunk_id_map: vec![(0, chunk(0, "b")), (1, chunk(1, "c"))]
+        .into_iter()
+        .collect(),
+      module_sizes: Default::default(),
+      module_stat_sizes: Default::default(),
+    };
+    analysis.augment_with_webpack_report(&report, 0);
+
+    // the second entrypoint's own chunk isn't one of the first one's
+    let c = analysis.all_nodes[analysis.node_map[&location("c.js")]]
+      .read()
+      .clone();
+    assert!(!c.tree_shaken);
+    assert_eq!(c.chunk, Some(1));
+  }
+
+  #[test]
+  fn it_sums_compressed_source_sizes_into_groups() {
+    let (resolver, mut analysis) = sample_analysis();
+    analysis.add_compressed_source_sizes();
+
+    let entrypoint = analysis.entrypoint.read().clone();
+    assert!(entrypoint.self_size.gzip.unwrap() > 0);
+    assert!(entrypoint.self_size.brotli.unwrap() > 0);
+
+    let module = group(&analysis, &resolver, "module");
+    let b = Location::new(resolver.resolve_root.as_ref().join("module/b.js")).unwrap();
+    let b = analysis.all_nodes[analysis.node_map[&b]].read().clone();
+    assert_eq!(
+      module.self_size.gzip,
+      Some(entrypoint.self_size.gzip.unwrap() + b.self_size.gzip.unwrap())
+    );
+  }
+
+  #[test]
+  fn it_updates_an_analysis_augmented_with_a_report() {
+    let directory = tempfile::tempdir().unwrap();
+    let write =
+      |path: &str, contents: &str| std::fs::write(directory.path().join(path), contents).unwrap();
+    write("index.js", "import a from './a';\nimport b from './b';\n");
+    write("a.js", "import c from './c';\n");
+    write("b.js", "export default 1;\n");
+    write("c.js", "export default 2;\n");
+    write("d.js", "import b from './b';\n");
+
+    let root = Location::new(directory.path()).unwrap();
+    let resolver = Resolver::new(&root, vec![]);
+    let location = |path: &str| Location::new(root.as_ref().join(path)).unwrap();
+    let entrypoint = location("index.js");
+
+    let mut cache = DependencyCache::new();
+    build_dependency_cache(&resolver, &entrypoint, &mut cache, &NoopLogger).unwrap();
+    let mut analysis =
+      Analysis::create_from_cache(&resolver, &cache, &entrypoint, &NoopLogger).unwrap();
+
+    // c.js was tree shaken
+    let chunk = Chunk {
+      id: 0,
+      name: "main".into(),
+      initial: true,
+      parents: vec![],
+      siblings: vec![],
+      children: vec![],
+      parsed_size: 0,
+      files: vec![],
+      gzip_size: None,
+      brotli_size: None,
+    };
+    let report = WebpackReport {
+      chunk_mapping: ["index.js", "a.js", "b.js"]
+        .iter()
+        .map(|path| (location(path), vec![chunk.clone()]))
+        .collect(),
+      chunk_id_map: vec![(0, chunk)].into_iter().collect(),
+      module_sizes: Default::default(),
+      module_stat_sizes: Default::default(),
+    };
+    analysis.augment_with_webpack_report(&report, 0);
+
+    write(
+      "a.js",
+      "import b from './b';\nimport c from './c';\nimport d from './d';\n",
+    );
+    let changed = crate::watch::update_dependency_cache(
+      &resolver,
+      &mut cache,
+      &[root.as_ref().join("a.js")],
+      &mut Diagnostics::default(),
+      &NoopLogger,
+    );
+    let delta = analysis
+      .update(&resolver, &cache, &changed, &NoopLogger)
+      .unwrap();
+    assert_eq!(delta.added_modules, vec![location("d.js")]);
+    assert_eq!(delta.added_imports.len(), 3);
+  }
+
+  #[test]
+  fn it_updates_after_a_file_changes() {
+    let directory = tempfile::tempdir().unwrap();
+    let write =
+      |path: &str, contents: &str| std::fs::write(directory.path().join(path), contents).unwrap();
+    write("index.js", "import a from './a';\nimport b from './b';\n");
+    write("a.js", "import c from './c';\n");
+    write("b.js", "export default 1;\n");
+    write("c.js", "export default 2;\n");
+    write("d.js", "import b from './b';\n");
+
+    let root = Location::new(directory.path()).unwrap();
+    let resolver = Resolver::new(&root, vec![]);
+    let location = |path: &str| Location::new(root.as_ref().join(path)).unwrap();
+    let entrypoint = location("index.js");
+
+    let mut cache = DependencyCache::new();
+    build_dependency_cache(&resolver, &entrypoint, &mut cache, &NoopLogger).unwrap();
+    let mut analysis =
+      Analysis::create_from_cache(&resolver, &cache, &entrypoint, &NoopLogger).unwrap();
+
+    write("a.js", "import d from './d';\nimport('./b');\n");
+    let changed = crate::watch::update_dependency_cache(
+      &resolver,
+      &mut cache,
+      &[
+        root.as_ref().join("a.js"),
+        root.as_ref().join("unrelated.txt"),
+      ],
+      &mut Diagnostics::default(),
+      &NoopLogger,
+    );
+    assert_eq!(changed, vec![location("a.js")]);
+
+    let delta = analysis
+      .update(&resolver, &cache, &changed, &NoopLogger)
+      .unwrap();
+    assert_eq!(delta.changed_modules, vec![location("a.js")]);
+    assert_eq!(delta.added_modules, vec![location("d.js")]);
+    assert_eq!(delta.removed_modules, vec![location("c.js")]);
+    assert_eq!(delta.added_imports.len(), 3);
+    assert_eq!(delta.removed_imports.len(), 1);
+
+    // the same graph as analysing from scratch
+    let fresh = Analysis::create_from_cache(&resolver, &cache, &entrypoint, &NoopLogger).unwrap();
+    let diff = fresh.diff(&analysis);
+    assert!(diff.added_modules.is_empty() && diff.removed_modules.is_empty());
+    assert!(diff.added_imports.is_empty() && diff.removed_imports.is_empty());
+    assert!(diff.group_changes.is_empty());
+    assert_eq!(analysis.node_map.len(), analysis.all_nodes.len());
+    assert_eq!(
+      analysis.edge_kinds[&(
+        analysis.node_map[&location("a.js")],
+        analysis.node_map[&location("b.js")]
+      )],
+      DependencyKind::AsyncImport
+    );
+  }
+
+  #[test]
+  fn it_reports_what_it_could_not_load() {
+    let directory = tempfile::tempdir().unwrap();
+    let write = |path: &str, contents: &[u8]| {
+      let path = directory.path().join(path);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, contents).unwrap()
+    };
+    write(
+      "index.js",
+      b"import a from './a';\nimport missing from './missing';\nimport bad from 'bad';\nimport binary from './binary';\n",
+    );
+    write("a.js", b"\n\nrequire('not-installed');\n");
+    write("binary.js", &[0xff, 0xfe, 0x00]);
+    write("node_modules/bad/package.json", b"{ not json");
+    write("node_modules/bad/index.js", b"");
+
+    let root = Location::new(directory.path()).unwrap();
+    let resolver = Resolver::new(&root, vec![]);
+    let location = |path: &str| Location::new(root.as_ref().join(path)).unwrap();
+    let entrypoint = location("index.js");
+
+    let mut cache = DependencyCache::new();
+    let diagnostics =
+      build_dependency_cache(&resolver, &entrypoint, &mut cache, &NoopLogger).unwrap();
+    let mut analysis =
+      Analysis::create_from_cache(&resolver, &cache, &entrypoint, &NoopLogger).unwrap();
+    analysis.add_diagnostics(diagnostics.clone());
+    assert_eq!(*analysis.diagnostics(), diagnostics);
+
+    let unresolved: Vec<(Location, String, Option<usize>, Option<usize>)> = diagnostics
+      .unresolved_imports
+      .iter()
+      .map(|import| {
+        (
+          import.importer.clone(),
+          import.specifier.to_string_lossy().to_string(),
+          import.line,
+          import.column,
+        )
+      })
+      .collect();
+    assert_eq!(
+      unresolved,
+      vec![
+        (
+          location("a.js"),
+          "not-installed".to_string(),
+          Some(3),
+          Some(9)
+        ),
+        (
+          location("index.js"),
+          "./missing".to_string(),
+          Some(2),
+          Some(21)
+        ),
+      ]
+    );
+
+    let file_errors: Vec<(Location, FileErrorKind)> = diagnostics
+      .file_errors
+      .iter()
+      .map(|error| (error.location.clone(), error.kind))
+      .collect();
+    assert_eq!(
+      file_errors,
+      vec![
+        (location("binary.js"), FileErrorKind::Unreadable),
+        (
+          location("node_modules/bad/package.json"),
+          FileErrorKind::MalformedPackageJson
+        ),
+      ]
+    );
+    assert!(diagnostics
+      .file_errors
+      .iter()
+      .all(|error| error.importer == Some(entrypoint.clone())));
+  }
 }
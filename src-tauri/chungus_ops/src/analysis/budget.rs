@@ -0,0 +1,403 @@
+use super::diff::format_bytes;
+use super::packages::package_directory;
+use super::{Analysis, Sizes};
+use crate::error::CoreError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs::OpenOptions;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Size limits for a bundle, read from a json or toml file. Paths are relative to the resolve
+/// root.
+///
+/// ```toml
+/// [[entrypoints]]
+/// path = "src/pages/Teacher.jsx"
+/// max_bytes = 250000
+///
+/// [[groups]]
+/// path = "src/components"
+/// max_modules = 200
+///
+/// [[packages]]
+/// name = "date-fns"
+/// max_bytes = 20000
+/// measure = "gzip"
+///
+/// [[chunks]]
+/// name = "main"
+/// max_bytes = 500000
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Budget {
+  pub entrypoints: Vec<PathLimit>,
+  /// Folders, everything under them counts
+  pub groups: Vec<PathLimit>,
+  /// Every installed copy of a package counts
+  pub packages: Vec<NamedLimit>,
+  /// By name, or by id for chunks without one
+  pub chunks: Vec<NamedLimit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathLimit {
+  pub path: PathBuf,
+  #[serde(flatten)]
+  pub limit: Limit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedLimit {
+  pub name: String,
+  #[serde(flatten)]
+  pub limit: Limit,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Limit {
+  pub max_bytes: Option<usize>,
+  pub max_modules: Option<usize>,
+  /// Which bytes `max_bytes` is in
+  #[serde(default)]
+  pub measure: Measure,
+}
+
+/// Bundled bytes are used by default. When a size hasn't been added to the analysis the source
+/// size is checked instead, which is never smaller. Parsed bytes fall back to webpack's stat size
+/// first, webpack stats don't have the bytes after minification.
+/// Gzip and brotli are measured on the emitted files for chunks and on the source files for
+/// everything else.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Measure {
+  Source,
+  #[default]
+  Parsed,
+  Stat,
+  Gzip,
+  Brotli,
+}
+
+impl Measure {
+  fn name(&self) -> &'static str {
+    match self {
+      Measure::Source => "source",
+      Measure::Parsed => "parsed",
+      Measure::Stat => "stat",
+      Measure::Gzip => "gzip",
+      Measure::Brotli => "brotli",
+    }
+  }
+
+  fn bytes(&self, sizes: &Sizes) -> usize {
+    match self {
+      Measure::Source => Some(sizes.source),
+      Measure::Parsed => sizes.parsed.or(sizes.stat),
+      Measure::Stat => sizes.stat,
+      Measure::Gzip => sizes.gzip,
+      Measure::Brotli => sizes.brotli,
+    }
+    .unwrap_or(sizes.source)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "name", rename_all = "snake_case")]
+pub enum BudgetTarget {
+  Entrypoint(PathBuf),
+  Group(PathBuf),
+  Package(String),
+  Chunk(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetViolation {
+  pub target: BudgetTarget,
+  /// The bytes that were measured, `None` when the module count is over
+  pub measure: Option<Measure>,
+  pub limit: usize,
+  pub actual: usize,
+}
+
+impl Budget {
+  /// Toml when the file ends in .toml, json otherwise
+  pub fn from_path(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+    let path = path.as_ref();
+    let file = OpenOptions::new().read(true).open(path)?;
+
+    if path
+      .extension()
+      .is_some_and(|extension| extension == "toml")
+    {
+      let mut contents = String::new();
+      BufReader::new(file).read_to_string(&mut contents)?;
+      Self::from_toml_str(&contents)
+    } else {
+      Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+  }
+
+  pub fn from_toml_str(contents: &str) -> Result<Self, CoreError> {
+    Ok(toml::from_str(contents)?)
+  }
+}
+
+// What a limit is checked against
+struct Usage {
+  sizes: Sizes,
+  modules: usize,
+}
+
+impl Limit {
+  fn check(&self, target: &BudgetTarget, usage: &Usage) -> Vec<BudgetViolation> {
+    let mut violations = vec![];
+    if let Some(limit) = self.max_bytes {
+      let actual = self.measure.bytes(&usage.sizes);
+      if actual > limit {
+        violations.push(BudgetViolation {
+          target: target.clone(),
+          measure: Some(self.measure),
+          limit,
+          actual,
+        });
+      }
+    }
+    if let Some(limit) = self.max_modules {
+      if usage.modules > limit {
+        violations.push(BudgetViolation {
+          target: target.clone(),
+          measure: None,
+          limit,
+          actual: usage.modules,
+        });
+      }
+    }
+    violations
+  }
+}
+
+impl Analysis {
+  /// Everything in `budget` that this analysis goes over. Limits on entrypoints, groups, packages
+  /// or chunks that aren't in the analysis always pass.
+  #[tracing::instrument(skip(self, budget))]
+  pub fn check_budget(&self, budget: &Budget) -> Vec<BudgetViolation> {
+    let mut violations = vec![];
+
+    for limit in budget.entrypoints.iter() {
+      if let Some(usage) = self.entrypoint_usage(&normalize(&limit.path)) {
+        violations.extend(
+          limit
+            .limit
+            .check(&BudgetTarget::Entrypoint(limit.path.clone()), &usage),
+        );
+      }
+    }
+
+    for limit in budget.groups.iter() {
+      if let Some(usage) = self.group_usage(&normalize(&limit.path)) {
+        violations.extend(
+          limit
+            .limit
+            .check(&BudgetTarget::Group(limit.path.clone()), &usage),
+        );
+      }
+    }
+
+    let packages = self.package_usage();
+    for limit in budget.packages.iter() {
+      if let Some(usage) = packages.get(&limit.name) {
+        violations.extend(
+          limit
+            .limit
+            .check(&BudgetTarget::Package(limit.name.clone()), usage),
+        );
+      }
+    }
+
+    for limit in budget.chunks.iter() {
+      if let Some(usage) = self.chunk_usage(&limit.name) {
+        violations.extend(
+          limit
+            .limit
+            .check(&BudgetTarget::Chunk(limit.name.clone()), &usage),
+        );
+      }
+    }
+
+    tracing::info!("{} budget violations", violations.len());
+    violations
+  }
+
+  // everything reachable from the entrypoint that is left in the bundle, other entrypoints of the
+  // analysis don't count
+  fn entrypoint_usage(&self, path: &Path) -> Option<Usage> {
+    let entrypoint = self
+      .entrypoint_indices()
+      .into_iter()
+      .find(|index| *self.all_nodes[*index].read().resolver_relative_path == *path)?;
+
+    let mut usage = Usage {
+      sizes: Sizes::default(),
+      modules: 0,
+    };
+    for node in self.all_nodes.iter() {
+      let node = node.read();
+      if !node.tree_shaken && node.entrypoints.contains(&entrypoint) {
+        usage.sizes = usage.sizes + node.self_size;
+        usage.modules += 1;
+      }
+    }
+    Some(usage)
+  }
+
+  // groups split by chunk are counted together, modules that were tree shaken aren't counted
+  fn group_usage(&self, path: &Path) -> Option<Usage> {
+    let mut inclusions = HashSet::new();
+    let mut found = false;
+    for group in self.analysis_groups.iter() {
+      let group = group.read();
+      if *group.resolver_relative_path != *path {
+        continue;
+      }
+      found = true;
+      inclusions.extend(group.inclusions.iter().copied());
+    }
+    if !found {
+      return None;
+    }
+
+    let mut usage = Usage {
+      sizes: Sizes::default(),
+      modules: 0,
+    };
+    for node in inclusions
+      .iter()
+      .filter_map(|index| self.all_nodes.get(*index))
+    {
+      let node = node.read();
+      if !node.tree_shaken {
+        usage.sizes = usage.sizes + node.self_size;
+        usage.modules += 1;
+      }
+    }
+    Some(usage)
+  }
+
+  fn package_usage(&self) -> BTreeMap<String, Usage> {
+    let mut packages: BTreeMap<String, Usage> = BTreeMap::new();
+    for node in self.all_nodes.iter() {
+      let node = node.read();
+      if node.tree_shaken {
+        continue;
+      }
+      if let Some((name, _)) =
+        package_directory(&node.resolver_relative_path, &self.module_directories)
+      {
+        let usage = packages.entry(name).or_insert(Usage {
+          sizes: Sizes::default(),
+          modules: 0,
+        });
+        usage.sizes = usage.sizes + node.self_size;
+        usage.modules += 1;
+      }
+    }
+    packages
+  }
+
+  fn chunk_usage(&self, name: &str) -> Option<Usage> {
+    let chunk = self
+      .chunks
+      .values()
+      .find(|chunk| chunk.name == name || chunk.id.to_string() == name)?;
+
+    Some(Usage {
+      sizes: Sizes {
+        source: chunk.parsed_size,
+        parsed: Some(chunk.parsed_size),
+        stat: None,
+        gzip: chunk.gzip_size,
+        brotli: chunk.brotli_size,
+      },
+      modules: self
+        .all_nodes
+        .iter()
+        .filter(|node| node.read().chunk == Some(chunk.id))
+        .count(),
+    })
+  }
+}
+
+// budgets are written by hand, "./src" is the same folder as "src"
+fn normalize(path: &Path) -> PathBuf {
+  path
+    .components()
+    .filter(|component| !matches!(component, std::path::Component::CurDir))
+    .collect()
+}
+
+impl Display for BudgetTarget {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BudgetTarget::Entrypoint(path) => write!(f, "entrypoint {}", path.to_string_lossy()),
+      BudgetTarget::Group(path) => write!(f, "folder {}", path.to_string_lossy()),
+      BudgetTarget::Package(name) => write!(f, "package {}", name),
+      BudgetTarget::Chunk(name) => write!(f, "chunk {}", name),
+    }
+  }
+}
+
+impl Display for BudgetViolation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self.measure {
+      Some(measure) => write!(
+        f,
+        "{} is {} {}, over its budget of {}",
+        self.target,
+        format_bytes(self.actual),
+        measure.name(),
+        format_bytes(self.limit)
+      ),
+      None => write!(
+        f,
+        "{} has {} modules, over its budget of {}",
+        self.target, self.actual, self.limit
+      ),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_reads_json_and_toml_budgets() {
+    let toml = r#"
+      [[entrypoints]]
+      path = "module/a.js"
+      max_bytes = 100
+
+      [[packages]]
+      name = "babel-polyfill"
+      max_modules = 1
+      max_bytes = 20000
+      measure = "gzip"
+    "#;
+    let json = r#"{
+      "entrypoints": [{ "path": "module/a.js", "max_bytes": 100 }],
+      "packages": [
+        { "name": "babel-polyfill", "max_modules": 1, "max_bytes": 20000, "measure": "gzip" }
+      ]
+    }"#;
+
+    let budget = Budget::from_toml_str(toml).unwrap();
+    assert_eq!(budget, serde_json::from_str(json).unwrap());
+    assert_eq!(budget.entrypoints[0].limit.measure, Measure::Parsed);
+    assert_eq!(budget.packages[0].limit.measure, Measure::Gzip);
+    assert_eq!(budget.packages[0].limit.max_modules, Some(1));
+    assert!(budget.groups.is_empty());
+  }
+}
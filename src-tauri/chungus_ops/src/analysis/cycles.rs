@@ -0,0 +1,161 @@
+use super::{Analysis, ImportStep};
+use crate::module::Location;
+use serde::{Deserialize, Serialize};
+
+/// A set of modules that all import each other, directly or through one another
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cycle {
+  pub members: Vec<Location>,
+  /// The imports that lead back to a module that is still being imported when walking the graph
+  /// from the entrypoint. Removing all of them breaks the cycle.
+  pub closing_edges: Vec<ImportStep>,
+}
+
+impl Analysis {
+  /// Every import cycle in the graph, in the order their first member was found
+  pub fn cycles(&self) -> &[Cycle] {
+    &self.cycles
+  }
+
+  /// The cycle `location` is part of, if any
+  pub fn cycle_of(&self, location: &Location) -> Option<&Cycle> {
+    let index = *self.node_map.get(location)?;
+    let cycle = self.all_nodes[index].read().cycle?;
+    self.cycles.get(cycle)
+  }
+
+  // Finds the strongly connected components of the graph and marks the nodes that are in one
+  #[tracing::instrument(skip(self))]
+  pub(super) fn find_cycles(&mut self) {
+    let adjacency = self.adjacency();
+    let (components, back_edges) = strongly_connected_components(&adjacency);
+
+    let mut cycles: Vec<Vec<usize>> = components
+      .into_iter()
+      .filter(|component| {
+        // a module can import itself
+        component.len() > 1 || adjacency[component[0]].contains(&component[0])
+      })
+      .map(|mut component| {
+        component.sort_unstable();
+        component
+      })
+      .collect();
+    cycles.sort_by_key(|component| component[0]);
+
+    for node in self.all_nodes.iter() {
+      node.write().cycle = None;
+    }
+    for (index, component) in cycles.iter().enumerate() {
+      for node in component.iter() {
+        self.all_nodes[*node].write().cycle = Some(index);
+      }
+    }
+
+    self.cycles = cycles
+      .iter()
+      .enumerate()
+      .map(|(index, component)| Cycle {
+        members: component
+          .iter()
+          .map(|node| self.all_nodes[*node].read().full_path.clone())
+          .collect(),
+        closing_edges: back_edges
+          .iter()
+          .filter(|(importer, _)| self.all_nodes[*importer].read().cycle == Some(index))
+          .map(|(importer, imported)| self.import_step(*importer, *imported))
+          .collect(),
+      })
+      .collect();
+
+    tracing::info!("Found {} import cycles", self.cycles.len());
+  }
+}
+
+// Tarjan's algorithm without recursion, dependency graphs get deep. Returns the components and
+// the edges that point back to a node on the current walk, which is what closes every cycle.
+fn strongly_connected_components(
+  adjacency: &[Vec<usize>],
+) -> (Vec<Vec<usize>>, Vec<(usize, usize)>) {
+  let mut order: Vec<Option<usize>> = vec![None; adjacency.len()];
+  let mut low_link = vec![0usize; adjacency.len()];
+  let mut on_stack = vec![false; adjacency.len()];
+  let mut on_walk = vec![false; adjacency.len()];
+  let mut stack = vec![];
+  let mut next_order = 0usize;
+
+  let mut components = vec![];
+  let mut back_edges = vec![];
+
+  for root in 0..adjacency.len() {
+    if order[root].is_some() {
+      continue;
+    }
+
+    // node and how many of its edges have been followed
+    let mut walk = vec![(root, 0usize)];
+    while let Some((node, edge)) = walk.pop() {
+      if edge == 0 {
+        order[node] = Some(next_order);
+        low_link[node] = next_order;
+        next_order += 1;
+        stack.push(node);
+        on_stack[node] = true;
+        on_walk[node] = true;
+      }
+
+      if let Some(next) = adjacency[node].get(edge).copied() {
+        walk.push((node, edge + 1));
+        match order[next] {
+          None => walk.push((next, 0)),
+          Some(seen_at) => {
+            if on_walk[next] {
+              back_edges.push((node, next));
+            }
+            if on_stack[next] {
+              low_link[node] = low_link[node].min(seen_at);
+            }
+          }
+        }
+        continue;
+      }
+
+      on_walk[node] = false;
+      if Some(low_link[node]) == order[node] {
+        let mut component = vec![];
+        while let Some(member) = stack.pop() {
+          on_stack[member] = false;
+          component.push(member);
+          if member == node {
+            break;
+          }
+        }
+        components.push(component);
+      }
+      if let Some((parent, _)) = walk.last() {
+        low_link[*parent] = low_link[*parent].min(low_link[node]);
+      }
+    }
+  }
+
+  (components, back_edges)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_finds_strongly_connected_components() {
+    // 0 -> 1 -> 2 -> 1, 2 -> 3, 3 -> 3, 4 -> 0
+    let adjacency = vec![vec![1], vec![2], vec![1, 3], vec![3], vec![0]];
+    let (mut components, back_edges) = strongly_connected_components(&adjacency);
+    for component in components.iter_mut() {
+      component.sort_unstable();
+    }
+    components.sort();
+
+    assert_eq!(components, vec![vec![0], vec![1, 2], vec![3], vec![4]]);
+    assert_eq!(back_edges, vec![(2, 1), (3, 3)]);
+  }
+}
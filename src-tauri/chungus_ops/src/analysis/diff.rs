@@ -0,0 +1,375 @@
+use super::packages::package_directory;
+use super::{Analysis, AnalysisNode, Sizes};
+use crate::module::RelativePath;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+
+/// What changed between two analyses of the same entrypoint, usually one from the main branch
+/// and one from a feature branch. Modules are matched by their path relative to the resolve root
+/// so both can come from different checkouts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalysisDiff {
+  pub entrypoint: RelativePath,
+  /// Every module reachable from the entrypoint
+  pub total_size: SizeChange,
+  pub added_modules: Vec<ModuleSize>,
+  pub removed_modules: Vec<ModuleSize>,
+  pub added_packages: Vec<String>,
+  pub removed_packages: Vec<String>,
+  pub chunk_changes: Vec<ChunkChange>,
+  /// Folders whose size changed, the largest change first
+  pub group_changes: Vec<GroupChange>,
+  pub added_imports: Vec<ImportEdge>,
+  pub removed_imports: Vec<ImportEdge>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeChange {
+  pub before: Sizes,
+  pub after: Sizes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleSize {
+  pub path: RelativePath,
+  pub size: Sizes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkChange {
+  pub path: RelativePath,
+  pub before: Option<usize>,
+  pub after: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupChange {
+  pub path: RelativePath,
+  pub size: SizeChange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ImportEdge {
+  pub importer: RelativePath,
+  pub imported: RelativePath,
+}
+
+impl SizeChange {
+  /// Bytes in the bundle when both sides have them, then webpack's stat sizes and bytes on disk
+  /// otherwise
+  pub fn delta(&self) -> i64 {
+    let (before, after) = self.bytes();
+    after as i64 - before as i64
+  }
+
+  fn bytes(&self) -> (usize, usize) {
+    match (
+      (self.before.parsed, self.after.parsed),
+      (self.before.stat, self.after.stat),
+    ) {
+      ((Some(before), Some(after)), _) | (_, (Some(before), Some(after))) => (before, after),
+      _ => (self.before.source, self.after.source),
+    }
+  }
+}
+
+impl Analysis {
+  /// Compares this analysis, the one before a change, with `after`
+  #[tracing::instrument(skip(self, after))]
+  pub fn diff(&self, after: &Analysis) -> AnalysisDiff {
+    let before_nodes = self.nodes_by_path();
+    let after_nodes = after.nodes_by_path();
+
+    let module_size = |(path, node): (&RelativePath, &AnalysisNode)| ModuleSize {
+      path: path.clone(),
+      size: node.self_size,
+    };
+    let added_modules = after_nodes
+      .iter()
+      .filter(|(path, _)| !before_nodes.contains_key(*path))
+      .map(module_size)
+      .collect();
+    let removed_modules = before_nodes
+      .iter()
+      .filter(|(path, _)| !after_nodes.contains_key(*path))
+      .map(module_size)
+      .collect();
+
+    let chunk_changes = after_nodes
+      .iter()
+      .filter_map(|(path, node)| {
+        let before = before_nodes.get(path)?;
+        if before.chunk == node.chunk {
+          return None;
+        }
+        Some(ChunkChange {
+          path: path.clone(),
+          before: before.chunk,
+          after: node.chunk,
+        })
+      })
+      .collect();
+
+    let before_packages = packages(&before_nodes, &self.module_directories);
+    let after_packages = packages(&after_nodes, &after.module_directories);
+
+    let before_imports = self.imports();
+    let after_imports = after.imports();
+
+    let total = |nodes: &BTreeMap<RelativePath, AnalysisNode>| {
+      nodes
+        .values()
+        .fold(Sizes::default(), |sizes, node| sizes + node.self_size)
+    };
+
+    AnalysisDiff {
+      entrypoint: after.entrypoint.read().resolver_relative_path.clone(),
+      total_size: SizeChange {
+        before: total(&before_nodes),
+        after: total(&after_nodes),
+      },
+      added_modules,
+      removed_modules,
+      added_packages: after_packages
+        .difference(&before_packages)
+        .cloned()
+        .collect(),
+      removed_packages: before_packages
+        .difference(&after_packages)
+        .cloned()
+        .collect(),
+      chunk_changes,
+      group_changes: group_changes(&self.group_sizes(), &after.group_sizes()),
+      added_imports: after_imports.difference(&before_imports).cloned().collect(),
+      removed_imports: before_imports.difference(&after_imports).cloned().collect(),
+    }
+  }
+
+  fn nodes_by_path(&self) -> BTreeMap<RelativePath, AnalysisNode> {
+    self
+      .all_nodes
+      .iter()
+      .map(|node| {
+        let node = node.read();
+        (node.resolver_relative_path.clone(), node.clone())
+      })
+      .collect()
+  }
+
+  pub(super) fn imports(&self) -> BTreeSet<ImportEdge> {
+    let paths: Vec<RelativePath> = self
+      .all_nodes
+      .iter()
+      .map(|node| node.read().resolver_relative_path.clone())
+      .collect();
+
+    self
+      .all_nodes
+      .iter()
+      .enumerate()
+      .flat_map(|(index, node)| {
+        let outgoing: Vec<usize> = node.read().outgoing.iter().copied().collect();
+        let paths = &paths;
+        outgoing.into_iter().map(move |imported| ImportEdge {
+          importer: paths[index].clone(),
+          imported: paths[imported].clone(),
+        })
+      })
+      .collect()
+  }
+
+  // groups split by chunk are added back together, chunk ids aren't stable between builds
+  fn group_sizes(&self) -> HashMap<RelativePath, Sizes> {
+    let mut sizes: HashMap<RelativePath, Sizes> = HashMap::new();
+    for group in self.analysis_groups.iter() {
+      let group = group.read();
+      let size = sizes
+        .entry(group.resolver_relative_path.clone())
+        .or_default();
+      *size = *size + group.inclusive_size;
+    }
+    sizes
+  }
+}
+
+fn packages(
+  nodes: &BTreeMap<RelativePath, AnalysisNode>,
+  module_directories: &[String],
+) -> BTreeSet<String> {
+  nodes
+    .keys()
+    .filter_map(|path| package_directory(path, module_directories).map(|(name, _)| name))
+    .collect()
+}
+
+fn group_changes(
+  before: &HashMap<RelativePath, Sizes>,
+  after: &HashMap<RelativePath, Sizes>,
+) -> Vec<GroupChange> {
+  let paths: BTreeSet<&RelativePath> = before.keys().chain(after.keys()).collect();
+  let mut changes: Vec<GroupChange> = paths
+    .into_iter()
+    .map(|path| GroupChange {
+      path: path.clone(),
+      size: SizeChange {
+        before: before.get(path).copied().unwrap_or_default(),
+        after: after.get(path).copied().unwrap_or_default(),
+      },
+    })
+    .filter(|change| change.size.before != change.size.after)
+    .collect();
+
+  // stable, so ties stay sorted by path
+  changes.sort_by_key(|change| std::cmp::Reverse(change.size.delta().abs()));
+  changes
+}
+
+pub(super) fn format_bytes(bytes: usize) -> String {
+  if bytes < 1024 {
+    format!("{} B", bytes)
+  } else if bytes < 1024 * 1024 {
+    format!("{:.1} KB", bytes as f64 / 1024.0)
+  } else {
+    format!("{:.2} MB", bytes as f64 / (1024.0 * 1024.0))
+  }
+}
+
+fn format_delta(delta: i64) -> String {
+  let sign = if delta < 0 { "-" } else { "+" };
+  format!("{}{}", sign, format_bytes(delta.unsigned_abs() as usize))
+}
+
+fn display_path(path: &RelativePath) -> String {
+  // the resolve root itself
+  if path.as_os_str().is_empty() {
+    return ".".to_string();
+  }
+  path.to_string_lossy().to_string()
+}
+
+impl Display for SizeChange {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let (before, after) = self.bytes();
+    write!(
+      f,
+      "{} -> {} ({})",
+      format_bytes(before),
+      format_bytes(after),
+      format_delta(self.delta())
+    )
+  }
+}
+
+impl Display for AnalysisDiff {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "{}: {}", display_path(&self.entrypoint), self.total_size)?;
+
+    if !self.added_packages.is_empty() {
+      writeln!(f, "\nAdded packages: {}", self.added_packages.join(", "))?;
+    }
+    if !self.removed_packages.is_empty() {
+      writeln!(
+        f,
+        "\nRemoved packages: {}",
+        self.removed_packages.join(", ")
+      )?;
+    }
+
+    for (title, sign, modules) in [
+      ("Added modules", '+', &self.added_modules),
+      ("Removed modules", '-', &self.removed_modules),
+    ]
+    .iter()
+    {
+      if modules.is_empty() {
+        continue;
+      }
+      writeln!(f, "\n{} ({}):", title, modules.len())?;
+      for module in modules.iter() {
+        writeln!(
+          f,
+          "  {} {} ({})",
+          sign,
+          display_path(&module.path),
+          format_bytes(module.size.bundled())
+        )?;
+      }
+    }
+
+    if !self.chunk_changes.is_empty() {
+      writeln!(f, "\nMoved between chunks:")?;
+      let chunk = |chunk: Option<usize>| {
+        chunk
+          .map(|chunk| chunk.to_string())
+          .unwrap_or_else(|| "none".to_string())
+      };
+      for change in self.chunk_changes.iter() {
+        writeln!(
+          f,
+          "  {}: {} -> {}",
+          display_path(&change.path),
+          chunk(change.before),
+          chunk(change.after)
+        )?;
+      }
+    }
+
+    if !self.group_changes.is_empty() {
+      writeln!(f, "\nFolders:")?;
+      for change in self.group_changes.iter() {
+        writeln!(f, "  {}: {}", display_path(&change.path), change.size)?;
+      }
+    }
+
+    for (title, imports) in [
+      ("New imports", &self.added_imports),
+      ("Removed imports", &self.removed_imports),
+    ]
+    .iter()
+    {
+      if imports.is_empty() {
+        continue;
+      }
+      writeln!(f, "\n{}:", title)?;
+      for import in imports.iter() {
+        writeln!(
+          f,
+          "  {} -> {}",
+          display_path(&import.importer),
+          display_path(&import.imported)
+        )?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_formats_size_changes() {
+    let sizes = |source: usize, parsed: Option<usize>| Sizes {
+      source,
+      parsed,
+      ..Default::default()
+    };
+
+    let change = SizeChange {
+      before: sizes(2048, Some(1000)),
+      after: sizes(4096, Some(50152)),
+    };
+    assert_eq!(change.delta(), 49152);
+    assert_eq!(change.to_string(), "1000 B -> 49.0 KB (+48.0 KB)");
+
+    // a side without a stats file falls back to the source for both
+    let change = SizeChange {
+      before: sizes(3 * 1024 * 1024, None),
+      after: sizes(1024, Some(512)),
+    };
+    assert_eq!(change.to_string(), "3.00 MB -> 1.0 KB (-3.00 MB)");
+  }
+}
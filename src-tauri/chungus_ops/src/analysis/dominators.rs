@@ -0,0 +1,205 @@
+use super::{Analysis, Sizes};
+use crate::module::Location;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// What would leave the bundle if nothing imported a module any more: the module itself and
+/// everything that is only reachable through it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetainedSize {
+  pub location: Location,
+  /// The closest module every import chain to this one goes through
+  pub immediate_dominator: Option<Location>,
+  pub retained_size: Sizes,
+  pub retained_modules: usize,
+}
+
+impl Analysis {
+  /// The immediate dominator of every node by index. `None` for the entrypoint and for nodes
+  /// that can't be reached from it.
+  pub fn dominator_tree(&self) -> Vec<Option<usize>> {
+    let entrypoint = self.entrypoint_index();
+    let mut dominators = immediate_dominators(&self.adjacency(), entrypoint);
+    dominators[entrypoint] = None;
+    dominators
+  }
+
+  /// Retained sizes of every module reachable from the entrypoint, the entrypoint included. Only
+  /// the first entrypoint of an analysis over several is used, modules only the others reach are
+  /// left out
+  #[tracing::instrument(skip(self))]
+  pub fn retained_sizes(&self) -> Vec<RetainedSize> {
+    let entrypoint = self.entrypoint_index();
+    let adjacency = self.adjacency();
+    let dominators = immediate_dominators(&adjacency, entrypoint);
+    let order = reverse_postorder(&adjacency, entrypoint);
+
+    // tree shaken modules are still in the graph but not in the bundle
+    let (mut sizes, mut modules): (Vec<Sizes>, Vec<usize>) = self
+      .all_nodes
+      .iter()
+      .map(|node| {
+        let node = node.read();
+        if node.tree_shaken {
+          (Sizes::default(), 0)
+        } else {
+          (node.self_size, 1)
+        }
+      })
+      .unzip();
+    // dominators come before the nodes they dominate, so going backwards every subtree is done
+    // before it is added to its parent
+    for node in order.iter().rev().copied() {
+      match dominators[node] {
+        Some(dominator) if dominator != node => {
+          sizes[dominator] = sizes[dominator] + sizes[node];
+          modules[dominator] += modules[node];
+        }
+        _ => {}
+      }
+    }
+
+    order
+      .into_iter()
+      .map(|node| RetainedSize {
+        location: self.all_nodes[node].read().full_path.clone(),
+        immediate_dominator: dominators[node]
+          .filter(|dominator| *dominator != node)
+          .map(|dominator| self.all_nodes[dominator].read().full_path.clone()),
+        retained_size: sizes[node],
+        retained_modules: modules[node],
+      })
+      .collect()
+  }
+
+  /// The `limit` modules that retain the most bytes, the best candidates for lazy loading or
+  /// removing. Bundled bytes are used when a stats file has been added, source bytes otherwise.
+  /// The entrypoint retains everything so it is left out.
+  pub fn largest_retained(&self, limit: usize) -> Vec<RetainedSize> {
+    let entrypoint = self.entrypoint.read().full_path.clone();
+    let mut retained: Vec<RetainedSize> = self
+      .retained_sizes()
+      .into_iter()
+      .filter(|retained| retained.location != entrypoint)
+      .collect();
+
+    // stable, so ties stay in graph order
+    retained.sort_by_key(|retained| Reverse(retained.retained_size.bundled()));
+    retained.truncate(limit);
+    retained
+  }
+
+  pub(crate) fn entrypoint_index(&self) -> usize {
+    *self
+      .node_map
+      .get(&self.entrypoint.read().full_path)
+      .unwrap_or(&0)
+  }
+}
+
+fn reverse_postorder(adjacency: &[Vec<usize>], root: usize) -> Vec<usize> {
+  let mut visited = vec![false; adjacency.len()];
+  let mut postorder = Vec::with_capacity(adjacency.len());
+  // node and how many of its edges have been followed
+  let mut stack = vec![(root, 0usize)];
+  visited[root] = true;
+
+  while let Some((node, edge)) = stack.pop() {
+    if let Some(next) = adjacency[node].get(edge).copied() {
+      stack.push((node, edge + 1));
+      if !visited[next] {
+        visited[next] = true;
+        stack.push((next, 0));
+      }
+    } else {
+      postorder.push(node);
+    }
+  }
+
+  postorder.reverse();
+  postorder
+}
+
+// Cooper, Harvey and Kennedy's "A Simple, Fast Dominance Algorithm". The root is its own
+// dominator.
+fn immediate_dominators(adjacency: &[Vec<usize>], root: usize) -> Vec<Option<usize>> {
+  let order = reverse_postorder(adjacency, root);
+  let mut position = vec![usize::MAX; adjacency.len()];
+  for (index, node) in order.iter().enumerate() {
+    position[*node] = index;
+  }
+
+  let mut predecessors: Vec<Vec<usize>> = vec![vec![]; adjacency.len()];
+  for (node, outgoing) in adjacency.iter().enumerate() {
+    if position[node] == usize::MAX {
+      continue;
+    }
+    for next in outgoing.iter() {
+      predecessors[*next].push(node);
+    }
+  }
+
+  let mut dominators: Vec<Option<usize>> = vec![None; adjacency.len()];
+  dominators[root] = Some(root);
+
+  let intersect = |dominators: &[Option<usize>], mut a: usize, mut b: usize| {
+    while a != b {
+      while position[a] > position[b] {
+        a = dominators[a].unwrap();
+      }
+      while position[b] > position[a] {
+        b = dominators[b].unwrap();
+      }
+    }
+    a
+  };
+
+  let mut changed = true;
+  while changed {
+    changed = false;
+    for node in order.iter().skip(1).copied() {
+      let mut processed = predecessors[node]
+        .iter()
+        .copied()
+        .filter(|predecessor| dominators[*predecessor].is_some());
+      let first = match processed.next() {
+        Some(first) => first,
+        None => continue,
+      };
+      let new_dominator = processed.fold(first, |dominator, predecessor| {
+        intersect(&dominators, predecessor, dominator)
+      });
+
+      if dominators[node] != Some(new_dominator) {
+        dominators[node] = Some(new_dominator);
+        changed = true;
+      }
+    }
+  }
+
+  dominators
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_finds_immediate_dominators() {
+    // 0 -> 1 -> 3 -> 4, 0 -> 2 -> 3, 1 -> 5 -> 1, 6 is unreachable
+    let adjacency = vec![
+      vec![1, 2],
+      vec![3, 5],
+      vec![3],
+      vec![4],
+      vec![],
+      vec![1],
+      vec![0],
+    ];
+
+    assert_eq!(
+      immediate_dominators(&adjacency, 0),
+      vec![Some(0), Some(0), Some(0), Some(0), Some(3), Some(1), None]
+    );
+  }
+}
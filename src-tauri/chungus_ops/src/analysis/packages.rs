@@ -0,0 +1,194 @@
+use super::{Analysis, Sizes};
+use crate::error::CoreError;
+use crate::module::Location;
+use crate::resolve::Resolver;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::OpenOptions;
+use std::io::BufReader;
+use std::path::{Component, Path, PathBuf};
+
+/// A package that is installed more than once in the graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicatePackage {
+  pub name: String,
+  pub copies: Vec<PackageCopy>,
+  /// What every copy but the largest one adds to the bundle
+  pub wasted_size: Sizes,
+}
+
+/// One install of a package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageCopy {
+  /// From its package.json, if it has one
+  pub version: Option<String>,
+  pub directory: Location,
+  pub modules: Vec<Location>,
+  /// Modules outside of this copy that import something in it
+  pub imported_by: Vec<Location>,
+  pub size: Sizes,
+}
+
+#[derive(Deserialize)]
+struct PackageVersion {
+  version: Option<String>,
+}
+
+impl Analysis {
+  /// Packages with more than one install directory in the graph, for example a nested
+  /// node_modules/react-is next to the hoisted one, with the most wasted bytes first
+  #[tracing::instrument(skip(self, resolver))]
+  pub fn duplicate_packages(
+    &self,
+    resolver: &Resolver,
+  ) -> Result<Vec<DuplicatePackage>, CoreError> {
+    // package name -> install directory -> nodes in it
+    let mut packages: BTreeMap<String, BTreeMap<PathBuf, Vec<usize>>> = BTreeMap::new();
+    for (index, node) in self.all_nodes.iter().enumerate() {
+      if let Some((name, directory)) =
+        package_directory(node.read().full_path.as_ref(), &resolver.module_directories)
+      {
+        packages
+          .entry(name)
+          .or_default()
+          .entry(directory)
+          .or_default()
+          .push(index);
+      }
+    }
+
+    let mut duplicates = vec![];
+    for (name, directories) in packages.into_iter() {
+      if directories.len() < 2 {
+        continue;
+      }
+
+      let copies = directories
+        .into_iter()
+        .map(|(directory, nodes)| self.package_copy(&directory, &nodes))
+        .collect::<Result<Vec<_>, CoreError>>()?;
+
+      let largest = copies
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, copy)| copy.size.source)
+        .map(|(index, _)| index)
+        .unwrap_or(0);
+      let wasted_size = copies
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != largest)
+        .fold(Sizes::default(), |sizes, (_, copy)| sizes + copy.size);
+
+      duplicates.push(DuplicatePackage {
+        name,
+        copies,
+        wasted_size,
+      });
+    }
+
+    // stable, so ties stay sorted by name
+    duplicates.sort_by_key(|duplicate| Reverse(duplicate.wasted_size.source));
+    Ok(duplicates)
+  }
+
+  fn package_copy(&self, directory: &Path, nodes: &[usize]) -> Result<PackageCopy, CoreError> {
+    let mut modules = vec![];
+    let mut imported_by = BTreeSet::new();
+    let mut size = Sizes::default();
+    for index in nodes.iter() {
+      let node = self.all_nodes[*index].read();
+      modules.push(node.full_path.clone());
+      size = size + node.self_size;
+
+      for importer in node.incoming.iter() {
+        let importer = &self.all_nodes[*importer].read().full_path;
+        if !importer.as_ref().starts_with(directory) {
+          imported_by.insert(importer.clone());
+        }
+      }
+    }
+    modules.sort();
+
+    Ok(PackageCopy {
+      version: package_version(directory),
+      directory: Location::new(directory)?,
+      modules,
+      imported_by: imported_by.into_iter().collect(),
+      size,
+    })
+  }
+}
+
+// The name and install directory of the package a file belongs to, from the innermost module
+// directory in its path. Scoped packages take two components.
+pub(super) fn package_directory(
+  path: &Path,
+  module_directories: &[impl AsRef<str>],
+) -> Option<(String, PathBuf)> {
+  let components: Vec<Component> = path.components().collect();
+  let module_directory = components.iter().rposition(|component| {
+    module_directories
+      .iter()
+      .any(|directory| component.as_os_str() == directory.as_ref())
+  })?;
+
+  let scope = components.get(module_directory + 1)?.as_os_str().to_str()?;
+  let name_length = if scope.starts_with('@') { 2 } else { 1 };
+  let end = module_directory + 1 + name_length;
+  // a file directly in node_modules isn't part of a package
+  if end >= components.len() {
+    return None;
+  }
+
+  let name = components[module_directory + 1..end]
+    .iter()
+    .map(|component| component.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/");
+  let directory = components[..end].iter().collect::<PathBuf>();
+  Some((name, directory))
+}
+
+fn package_version(directory: &Path) -> Option<String> {
+  let file = OpenOptions::new()
+    .read(true)
+    .open(directory.join("package.json"))
+    .ok()?;
+  match serde_json::from_reader::<_, PackageVersion>(BufReader::new(file)) {
+    Ok(package) => package.version,
+    Err(e) => {
+      tracing::warn!("Could not read the version of {:?}: {}", directory, e);
+      None
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_finds_the_package_a_file_belongs_to() {
+    let module_directories = vec!["node_modules".to_string()];
+    let package_of = |path: &str| package_directory(Path::new(path), &module_directories);
+
+    assert_eq!(
+      package_of("/app/node_modules/react-is/cjs/react-is.js"),
+      Some((
+        "react-is".to_string(),
+        PathBuf::from("/app/node_modules/react-is")
+      ))
+    );
+    assert_eq!(
+      package_of("/app/node_modules/a/node_modules/@scope/b/index.js"),
+      Some((
+        "@scope/b".to_string(),
+        PathBuf::from("/app/node_modules/a/node_modules/@scope/b")
+      ))
+    );
+    assert_eq!(package_of("/app/node_modules/loose.js"), None);
+    assert_eq!(package_of("/app/src/index.js"), None);
+  }
+}
@@ -0,0 +1,173 @@
+use super::Analysis;
+use crate::error::CoreError;
+use crate::module::{DependencyKind, Location};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+/// One import on the way from the entrypoint to a module
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportStep {
+  pub importer: Location,
+  pub imported: Location,
+  pub kind: DependencyKind,
+}
+
+impl Analysis {
+  /// The shortest import chains from the entrypoint to `location`, at most `limit` of them.
+  /// Every chain after the first is the next shortest one that differs from all the others, so
+  /// `limit` of 1 answers "why is this included" and more show the other ways it gets pulled in.
+  /// The entrypoint itself is reached by one empty chain. Chains only start from the first
+  /// entrypoint of an analysis over several.
+  #[tracing::instrument(skip(self))]
+  pub fn paths_to(
+    &self,
+    location: &Location,
+    limit: usize,
+  ) -> Result<Vec<Vec<ImportStep>>, CoreError> {
+    let target = *self
+      .node_map
+      .get(location)
+      .ok_or_else(|| CoreError::custom(&format!("{:?} is not part of the analysis", location)))?;
+    let chains = k_shortest_paths(&self.adjacency(), self.entrypoint_index(), target, limit);
+    Ok(
+      chains
+        .into_iter()
+        .map(|chain| {
+          chain
+            .windows(2)
+            .map(|edge| self.import_step(edge[0], edge[1]))
+            .collect()
+        })
+        .collect(),
+    )
+  }
+
+  pub(super) fn import_step(&self, importer: usize, imported: usize) -> ImportStep {
+    ImportStep {
+      importer: self.all_nodes[importer].read().full_path.clone(),
+      imported: self.all_nodes[imported].read().full_path.clone(),
+      kind: self
+        .edge_kinds
+        .get(&(importer, imported))
+        .copied()
+        .unwrap_or(DependencyKind::Import),
+    }
+  }
+}
+
+// Yen's algorithm, every edge costs the same so the shortest paths are found with a bfs
+fn k_shortest_paths(
+  adjacency: &[Vec<usize>],
+  source: usize,
+  target: usize,
+  limit: usize,
+) -> Vec<Vec<usize>> {
+  let mut found: Vec<Vec<usize>> = vec![];
+  if limit == 0 {
+    return found;
+  }
+  match shortest_path(adjacency, source, target, &HashSet::new(), &HashSet::new()) {
+    Some(path) => found.push(path),
+    None => return found,
+  }
+
+  let mut candidates: Vec<Vec<usize>> = vec![];
+  while found.len() < limit {
+    let previous = found.last().unwrap().clone();
+
+    for spur_index in 0..previous.len() - 1 {
+      let spur_node = previous[spur_index];
+      let root = &previous[..=spur_index];
+
+      // don't find a path that has been found already
+      let removed_edges: HashSet<(usize, usize)> = found
+        .iter()
+        .filter(|path| path.len() > spur_index + 1 && &path[..=spur_index] == root)
+        .map(|path| (path[spur_index], path[spur_index + 1]))
+        .collect();
+      // and keep it loop free
+      let removed_nodes: HashSet<usize> = root[..spur_index].iter().copied().collect();
+
+      if let Some(spur) =
+        shortest_path(adjacency, spur_node, target, &removed_edges, &removed_nodes)
+      {
+        let mut candidate = root[..spur_index].to_vec();
+        candidate.extend(spur);
+        if !candidates.contains(&candidate) && !found.contains(&candidate) {
+          candidates.push(candidate);
+        }
+      }
+    }
+
+    // the first of the shortest candidates, so ties come out in a stable order
+    let next = candidates
+      .iter()
+      .enumerate()
+      .min_by_key(|(_, candidate)| candidate.len())
+      .map(|(index, _)| index);
+    match next {
+      Some(index) => found.push(candidates.remove(index)),
+      None => break,
+    }
+  }
+
+  found
+}
+
+fn shortest_path(
+  adjacency: &[Vec<usize>],
+  source: usize,
+  target: usize,
+  removed_edges: &HashSet<(usize, usize)>,
+  removed_nodes: &HashSet<usize>,
+) -> Option<Vec<usize>> {
+  let mut previous: Vec<Option<usize>> = vec![None; adjacency.len()];
+  let mut visited = vec![false; adjacency.len()];
+  let mut queue = VecDeque::new();
+  visited[source] = true;
+  queue.push_back(source);
+
+  while let Some(node) = queue.pop_front() {
+    if node == target {
+      let mut path = vec![target];
+      let mut current = target;
+      while let Some(parent) = previous[current] {
+        path.push(parent);
+        current = parent;
+      }
+      path.reverse();
+      return Some(path);
+    }
+
+    for next in adjacency[node].iter().copied() {
+      if visited[next] || removed_nodes.contains(&next) || removed_edges.contains(&(node, next)) {
+        continue;
+      }
+      visited[next] = true;
+      previous[next] = Some(node);
+      queue.push_back(next);
+    }
+  }
+
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_finds_the_k_shortest_paths() {
+    // 0 -> 1 -> 3, 0 -> 2 -> 3, 0 -> 1 -> 2 -> 3, and 3 -> 0 closing a cycle
+    let adjacency = vec![vec![1, 2], vec![2, 3], vec![3], vec![0]];
+
+    assert_eq!(k_shortest_paths(&adjacency, 0, 3, 1), vec![vec![0, 1, 3]]);
+    assert_eq!(
+      k_shortest_paths(&adjacency, 0, 3, 5),
+      vec![vec![0, 1, 3], vec![0, 2, 3], vec![0, 1, 2, 3]]
+    );
+    assert_eq!(k_shortest_paths(&adjacency, 0, 0, 3), vec![vec![0]]);
+    assert!(k_shortest_paths(&adjacency, 0, 3, 0).is_empty());
+    assert!(k_shortest_paths(&[vec![], vec![]], 0, 1, 3).is_empty());
+  }
+}
@@ -0,0 +1,269 @@
+use super::{Analysis, ImportEdge, Sizes};
+use crate::compression::compressed_file_sizes;
+use crate::error::CoreError;
+use crate::logging::ClientSideLogger;
+use crate::module::{DependencyKind, Location, ModuleKind};
+use crate::resolve::Resolver;
+use crate::DependencyCache;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// What an update after a file change did to the analysis
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalysisDelta {
+  /// Modules in the graph that were parsed again
+  pub changed_modules: Vec<Location>,
+  /// Modules that are now imported and weren't before
+  pub added_modules: Vec<Location>,
+  /// Modules that no entrypoint reaches anymore, they are taken out of the graph
+  pub removed_modules: Vec<Location>,
+  pub added_imports: Vec<ImportEdge>,
+  pub removed_imports: Vec<ImportEdge>,
+}
+
+impl AnalysisDelta {
+  pub fn is_empty(&self) -> bool {
+    self.changed_modules.is_empty()
+      && self.added_modules.is_empty()
+      && self.removed_modules.is_empty()
+      && self.added_imports.is_empty()
+      && self.removed_imports.is_empty()
+  }
+}
+
+impl Analysis {
+  /// Relinks the modules at `changed` after they were parsed again into `cache`, see
+  /// `watch::update_dependency_cache`. Modules they now import are added to the graph and the
+  /// ones nothing reaches anymore are taken out. Everything else is left as it is, chunks from a
+  /// stats file included.
+  #[tracing::instrument(skip(self, resolver, cache, changed, logger))]
+  pub fn update(
+    &mut self,
+    resolver: &Resolver,
+    cache: &DependencyCache,
+    changed: &[Location],
+    logger: &impl ClientSideLogger,
+  ) -> Result<AnalysisDelta, CoreError> {
+    let before = self.imports();
+    let mut delta = AnalysisDelta::default();
+
+    let first_new_node = self.all_nodes.len();
+    for location in changed.iter() {
+      if let Some(index) = self.node_map.get(location).copied() {
+        self.relink(resolver, cache, index, logger)?;
+        delta.changed_modules.push(location.clone());
+      }
+    }
+    delta.added_modules = self.all_nodes[first_new_node..]
+      .iter()
+      .map(|node| node.read().full_path.clone())
+      .collect();
+
+    delta.removed_modules = self.remove_unreachable();
+    // added by one change and dropped by another
+    let removed_modules = &delta.removed_modules;
+    delta
+      .added_modules
+      .retain(|location| !removed_modules.contains(location));
+    self.link_groups();
+    self.tag_entrypoints();
+    self.find_cycles();
+    self.aggregate_sizes();
+    self.find_unresolved_imports(cache);
+    if !delta.added_modules.is_empty() || !delta.removed_modules.is_empty() {
+      self.create_file_tree(resolver, logger)?;
+    }
+
+    let after = self.imports();
+    delta.added_imports = after.difference(&before).cloned().collect();
+    delta.removed_imports = before.difference(&after).cloned().collect();
+
+    tracing::info!(
+      "Updated {} modules, {} added and {} removed",
+      delta.changed_modules.len(),
+      delta.added_modules.len(),
+      delta.removed_modules.len()
+    );
+    Ok(delta)
+  }
+
+  // Points the outgoing edges of a node at what its module in the cache imports now
+  fn relink(
+    &mut self,
+    resolver: &Resolver,
+    cache: &DependencyCache,
+    index: usize,
+    logger: &impl ClientSideLogger,
+  ) -> Result<(), CoreError> {
+    let location = self.all_nodes[index].read().full_path.clone();
+    let module = cache
+      .get(&location)
+      .ok_or_else(|| CoreError::custom(&format!("{:?} is not in the cache", &location)))?;
+
+    let mut dependencies: Vec<(Location, DependencyKind)> = vec![];
+    for dependency in module.dependencies.iter() {
+      if let Some(target) = dependency.location() {
+        // the first import wins, like when the graph was populated
+        if !dependencies.iter().any(|(existing, _)| *existing == target) {
+          dependencies.push((target, dependency.kind()));
+        }
+      }
+    }
+
+    let mut outgoing = HashSet::new();
+    let mut new_nodes = vec![];
+    for (target, kind) in dependencies {
+      let target_index = match self.node_map.get(&target).copied() {
+        Some(target_index) => {
+          self.all_nodes[target_index].write().incoming.insert(index);
+          target_index
+        }
+        None => {
+          let is_node_module = cache
+            .get(&target)
+            .map(|module| module.kind == ModuleKind::NodeModule)
+            .unwrap_or(false);
+          self.insert_node(resolver, target, is_node_module, Some(index))?;
+          new_nodes.push(self.all_nodes.len() - 1);
+          self.all_nodes.len() - 1
+        }
+      };
+      outgoing.insert(target_index);
+      self.edge_kinds.insert((index, target_index), kind);
+    }
+
+    let previous = std::mem::replace(
+      &mut self.all_nodes[index].write().outgoing,
+      outgoing.clone(),
+    );
+    for target_index in previous.difference(&outgoing).copied() {
+      self.all_nodes[target_index].write().incoming.remove(&index);
+      self.edge_kinds.remove(&(index, target_index));
+    }
+
+    {
+      let mut node = self.all_nodes[index].write();
+      let compressed = node.self_size.gzip.is_some();
+      node.self_size = Sizes {
+        parsed: node.self_size.parsed,
+        stat: node.self_size.stat,
+        ..Sizes::from_disk(&location)
+      };
+      if compressed {
+        match compressed_file_sizes(&location) {
+          Ok(sizes) => {
+            node.self_size.gzip = Some(sizes.gzip);
+            node.self_size.brotli = Some(sizes.brotli);
+          }
+          Err(e) => tracing::warn!("Could not compress {:?}: {}", &location, e),
+        }
+      }
+      node.inclusive_size = node.self_size;
+    }
+
+    for new_node in new_nodes {
+      self.populate(resolver, cache, new_node, logger)?;
+    }
+    Ok(())
+  }
+
+  // Takes every node no entrypoint reaches out of the graph, along with groups that end up empty.
+  // Returns the locations of the nodes that were taken out.
+  fn remove_unreachable(&mut self) -> Vec<Location> {
+    let adjacency = self.adjacency();
+    let mut reachable = vec![false; self.all_nodes.len()];
+    let mut queue = self.entrypoint_indices();
+    for entrypoint in queue.iter() {
+      reachable[*entrypoint] = true;
+    }
+    while let Some(node) = queue.pop() {
+      for next in adjacency[node].iter().copied() {
+        if !reachable[next] {
+          reachable[next] = true;
+          queue.push(next);
+        }
+      }
+    }
+
+    if reachable.iter().all(|reachable| *reachable) {
+      return vec![];
+    }
+
+    // old index -> new index
+    let mut new_indices: Vec<Option<usize>> = vec![None; self.all_nodes.len()];
+    let mut kept = vec![];
+    let mut removed = vec![];
+    for (index, node) in std::mem::take(&mut self.all_nodes).into_iter().enumerate() {
+      if reachable[index] {
+        new_indices[index] = Some(kept.len());
+        kept.push(node);
+      } else {
+        removed.push(node.read().full_path.clone());
+      }
+    }
+    self.all_nodes = kept;
+
+    let remap = |indices: &HashSet<usize>| -> HashSet<usize> {
+      indices
+        .iter()
+        .filter_map(|index| new_indices[*index])
+        .collect()
+    };
+    for node in self.all_nodes.iter() {
+      let mut node = node.write();
+      node.incoming = remap(&node.incoming);
+      node.outgoing = remap(&node.outgoing);
+    }
+    self.entrypoints = self
+      .entrypoints
+      .iter()
+      .filter_map(|index| new_indices[*index])
+      .collect();
+    self.node_map = self
+      .all_nodes
+      .iter()
+      .enumerate()
+      .map(|(index, node)| (node.read().full_path.clone(), index))
+      .collect();
+    self.edge_kinds = self
+      .edge_kinds
+      .iter()
+      .filter_map(|((from, to), kind)| Some(((new_indices[*from]?, new_indices[*to]?), *kind)))
+      .collect();
+
+    let remap_list = |indices: &[usize]| -> Vec<usize> {
+      indices
+        .iter()
+        .filter_map(|index| new_indices[*index])
+        .collect()
+    };
+    for group in self.analysis_groups.iter() {
+      let mut group = group.write();
+      group.inclusions = remap_list(&group.inclusions);
+      group.immediate_children = remap_list(&group.immediate_children);
+    }
+    self
+      .analysis_groups
+      .retain(|group| !group.read().inclusions.is_empty());
+    self.index_groups();
+
+    removed
+  }
+
+  // Groups import everything their modules import and are imported by everything that imports
+  // one of their modules
+  fn link_groups(&self) {
+    for group in self.analysis_groups.iter() {
+      let mut group = group.write();
+      let mut incoming = HashSet::new();
+      let mut outgoing = HashSet::new();
+      for index in group.inclusions.iter() {
+        let node = self.all_nodes[*index].read();
+        incoming.extend(node.incoming.iter().copied());
+        outgoing.extend(node.outgoing.iter().copied());
+      }
+      group.incoming = incoming;
+      group.outgoing = outgoing;
+    }
+  }
+}
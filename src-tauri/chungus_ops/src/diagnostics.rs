@@ -0,0 +1,202 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CoreError;
+use crate::module::{Asset, DependencyKind, Location, Module, ModuleKind};
+use crate::parser::ImportSpans;
+
+/// What couldn't be understood while the dependency cache was built, so imports don't silently
+/// go missing from an analysis
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostics {
+  pub unresolved_imports: Vec<UnresolvedImportDiagnostic>,
+  /// Files that were imported but couldn't be loaded, they and everything only they import are
+  /// missing from the cache
+  pub file_errors: Vec<FileError>,
+}
+
+/// An import whose specifier doesn't point to a file
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct UnresolvedImportDiagnostic {
+  /// The module or package.json the import is in
+  pub importer: Location,
+  pub specifier: PathBuf,
+  pub kind: DependencyKind,
+  /// Where the specifier starts, 1 based
+  pub line: Option<usize>,
+  pub column: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FileError {
+  pub location: Location,
+  pub importer: Option<Location>,
+  pub kind: FileErrorKind,
+  pub message: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileErrorKind {
+  /// It couldn't be opened, or isn't text
+  Unreadable,
+  /// Not valid json, or its entrypoint doesn't exist
+  MalformedPackageJson,
+}
+
+impl Diagnostics {
+  pub fn is_empty(&self) -> bool {
+    self.unresolved_imports.is_empty() && self.file_errors.is_empty()
+  }
+
+  /// Records every import of `module` that didn't resolve. Imports that weren't parsed from source
+  /// have no position, their importing file is read again to find the line they are on.
+  pub fn add_unresolved_imports(&mut self, module: &Module) {
+    let unresolved: Vec<(&PathBuf, DependencyKind, Option<ImportSpans>)> = module
+      .dependencies
+      .iter()
+      .filter_map(|dependency| match dependency.asset() {
+        Asset::Unresolved(specifier) => Some((specifier, dependency.kind(), dependency.spans())),
+        _ => None,
+      })
+      .collect();
+    if unresolved.is_empty() {
+      return;
+    }
+
+    // node modules are keyed by their entrypoint, their dependencies are in the package.json
+    // next to it
+    let importer = match module.kind {
+      ModuleKind::NormalModule => Some(module.location.clone()),
+      ModuleKind::NodeModule => module
+        .location
+        .as_ref()
+        .ancestors()
+        .map(|directory| directory.join("package.json"))
+        .find(|package_json| package_json.is_file())
+        .and_then(|package_json| Location::new(package_json).ok()),
+    };
+    let importer = match importer {
+      Some(importer) => importer,
+      None => module.location.clone(),
+    };
+    let contents = if unresolved.iter().any(|(_, _, spans)| spans.is_none()) {
+      std::fs::read_to_string(&importer).unwrap_or_default()
+    } else {
+      String::new()
+    };
+
+    for (specifier, kind, spans) in unresolved {
+      let (line, column) = match spans {
+        Some(spans) => (
+          Some(spans.specifier.start.line),
+          Some(spans.specifier.start.column),
+        ),
+        None => (specifier_line(&contents, specifier), None),
+      };
+      self.unresolved_imports.push(UnresolvedImportDiagnostic {
+        importer: importer.clone(),
+        specifier: specifier.clone(),
+        kind,
+        line,
+        column,
+      });
+    }
+  }
+
+  /// Records that the file at `location` couldn't be loaded
+  pub fn add_file_error(
+    &mut self,
+    location: &Location,
+    importer: Option<&Location>,
+    is_package_json: bool,
+    error: &CoreError,
+  ) {
+    tracing::warn!("Could not load {:?}: {}", location, error);
+    self.file_errors.push(FileError {
+      location: location.clone(),
+      importer: importer.cloned(),
+      kind: if is_package_json && !error.is_io_error() {
+        FileErrorKind::MalformedPackageJson
+      } else {
+        FileErrorKind::Unreadable
+      },
+      message: error.to_string(),
+    });
+  }
+
+  /// Adds everything in `other` that isn't in here yet
+  pub fn merge(&mut self, other: Diagnostics) {
+    self.unresolved_imports.extend(other.unresolved_imports);
+    self.file_errors.extend(other.file_errors);
+    self.sort();
+  }
+
+  /// Sorts by file and drops duplicates
+  pub fn sort(&mut self) {
+    self.unresolved_imports.sort();
+    self.unresolved_imports.dedup();
+    self.file_errors.sort();
+    self.file_errors.dedup();
+  }
+}
+
+// The first line with the specifier in quotes on it, for package.json dependencies
+fn specifier_line(contents: &str, specifier: &Path) -> Option<usize> {
+  let specifier = specifier.to_string_lossy();
+  let quoted: Vec<String> = ['"', '\'', '`']
+    .iter()
+    .map(|quote| format!("{}{}{}", quote, specifier, quote))
+    .collect();
+  contents
+    .lines()
+    .position(|line| quoted.iter().any(|quoted| line.contains(quoted.as_str())))
+    .map(|index| index + 1)
+}
+
+impl Display for Diagnostics {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    for import in self.unresolved_imports.iter() {
+      write!(f, "{}", import.importer.as_ref().display())?;
+      if let Some(line) = import.line {
+        write!(f, ":{}", line)?;
+      }
+      if let Some(column) = import.column {
+        write!(f, ":{}", column)?;
+      }
+      writeln!(
+        f,
+        ": could not resolve {:?}",
+        import.specifier.to_string_lossy()
+      )?;
+    }
+
+    for error in self.file_errors.iter() {
+      let kind = match error.kind {
+        FileErrorKind::Unreadable => "could not read",
+        FileErrorKind::MalformedPackageJson => "malformed package.json",
+      };
+      write!(f, "{}: {}", error.location.as_ref().display(), kind)?;
+      if let Some(importer) = &error.importer {
+        write!(f, ", imported by {}", importer.as_ref().display())?;
+      }
+      writeln!(f, ": {}", error.message)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_finds_the_line_of_a_specifier() {
+    let contents = "import a from \"./a\";\n// ./b\nconst b = require('./b');\n";
+    assert_eq!(specifier_line(contents, Path::new("./a")), Some(1));
+    assert_eq!(specifier_line(contents, Path::new("./b")), Some(3));
+    assert_eq!(specifier_line(contents, Path::new("./c")), None);
+  }
+}
@@ -18,6 +18,13 @@ impl CoreError {
   }
 }
 
+impl CoreError {
+  /// Whether this came from reading or writing a file
+  pub fn is_io_error(&self) -> bool {
+    self.source_error.is::<std::io::Error>()
+  }
+}
+
 impl Display for CoreError {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     self.source_error.fmt(f)
@@ -58,6 +65,24 @@ impl From<serde_json::Error> for CoreError {
   }
 }
 
+impl From<toml::de::Error> for CoreError {
+  fn from(err: toml::de::Error) -> Self {
+    tracing::error!("{}", err.to_string());
+    Self {
+      source_error: Box::new(err),
+    }
+  }
+}
+
+impl From<notify::Error> for CoreError {
+  fn from(err: notify::Error) -> Self {
+    tracing::error!("{}", err.to_string());
+    Self {
+      source_error: Box::new(err),
+    }
+  }
+}
+
 impl From<CoreError> for String {
   fn from(error: CoreError) -> Self {
     error.to_string()
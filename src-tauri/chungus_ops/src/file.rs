@@ -1,7 +1,7 @@
 use crate::error::CoreError;
-use crate::module::{Location, Module, ModuleKind, RelativePath};
+use crate::module::{DependencyKind, Location, Module, ModuleKind, RelativePath};
 use crate::parser::Import::NodeDependency;
-use crate::parser::UnresolvedImport;
+use crate::parser::{ScanMode, UnresolvedImport};
 use crate::resolve::Resolver;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -143,7 +143,8 @@ pub fn process_javascript_file(
     contents
   };
 
-  let unresolved_dependencies = UnresolvedImport::parse_many(&file_contents)?;
+  let unresolved_dependencies =
+    UnresolvedImport::parse_many_with_mode(&file_contents, ScanMode::for_path(location))?;
   let module = resolve.resolve_normal_module(&location, &unresolved_dependencies);
 
   tracing::trace!(
@@ -157,24 +158,19 @@ pub fn process_javascript_file(
 pub fn process_package_json(
   resolve: &Resolver,
   package_json_location: &Location,
+  kind: DependencyKind,
 ) -> Result<Module, CoreError> {
   let file = OpenOptions::new()
     .read(true)
     .open(package_json_location.as_ref())?;
   let reader = BufReader::new(file);
 
-  let value: serde_json::Value = serde_json::from_reader(reader).unwrap();
-  let main_file = value["module"]
-    .as_str()
-    .or(value["main"].as_str())
-    .unwrap_or("index.js");
-  let main_file_path = Location::new(
-    package_json_location
-      .as_ref()
-      .parent()
-      .unwrap()
-      .join(&main_file),
-  )?;
+  let value: serde_json::Value = serde_json::from_reader(reader)?;
+  let main_file_path = Location::new(resolve.package_entrypoint(
+    package_json_location.as_ref().parent().unwrap(),
+    &value,
+    kind,
+  ))?;
 
   let dependencies = resolve.resolve_package_json_dependencies(&package_json_location, &value);
   tracing::trace!(
@@ -206,6 +202,7 @@ mod tests {
           .join("./src/sample_javascript/node_modules/babel-polyfill/package.json"),
       )
       .unwrap(),
+      DependencyKind::Import,
     );
 
     dbg!(module);
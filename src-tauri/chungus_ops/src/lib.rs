@@ -4,6 +4,7 @@ use std::sync::Arc;
 
 pub use parking_lot::RwLock;
 
+use crate::diagnostics::Diagnostics;
 use crate::error::CoreError;
 use crate::file::{process_javascript_file, process_package_json};
 use crate::logging::ClientSideLogger;
@@ -11,15 +12,23 @@ use crate::module::{Asset, Location, Module};
 use crate::resolve::Resolver;
 
 pub mod analysis;
+pub mod compression;
 pub mod dependency_graph;
+pub mod diagnostics;
 pub mod error;
 pub mod file;
 pub mod logging;
 pub mod module;
 pub mod module_cache;
+pub mod package_exports;
+pub mod parallel_cache;
 pub mod parser;
+pub mod persistent_cache;
 pub mod resolve;
+pub mod tsconfig;
+pub mod watch;
 pub mod webpack_report;
+pub mod webpack_resolve;
 pub fn start_resolve_project(
   project_root: impl AsRef<Path>,
   included_directories: Vec<PathBuf>,
@@ -28,13 +37,16 @@ pub fn start_resolve_project(
   Ok(Resolver::new(&location, included_directories))
 }
 
+/// Reads the module at `target` and everything it imports into `cache`. Only the entrypoint has
+/// to load, imports that don't resolve and files that can't be read are in the diagnostics that
+/// come back.
 #[tracing::instrument(skip(cache, target, resolver, logger))]
 pub fn build_dependency_cache(
   resolver: &Resolver,
   target: impl AsRef<Path>,
   cache: &mut HashMap<Location, Module>,
   logger: &impl ClientSideLogger,
-) -> Result<(), CoreError> {
+) -> Result<Diagnostics, CoreError> {
   let file = Location::new(target)?;
 
   tracing::info!("Start build dependency cache {:?}", &file);
@@ -42,22 +54,31 @@ pub fn build_dependency_cache(
   let root_module = process_javascript_file(&resolver, &file)?;
   logger.message("Loaded root module");
 
+  let mut diagnostics = Diagnostics::default();
+  diagnostics.add_unresolved_imports(&root_module);
   cache.insert(file.clone(), root_module.clone());
-  recursively_build_dependency_tree(cache, &resolver, root_module, &*logger)?;
-
-  tracing::info!("Built dependency cache {:?}", &file);
-  Ok(())
+  recursively_build_dependency_tree(cache, &resolver, root_module, &mut diagnostics, &*logger);
+  diagnostics.sort();
+
+  tracing::info!(
+    "Built dependency cache {:?}, {} unresolved imports and {} files that could not be loaded",
+    &file,
+    diagnostics.unresolved_imports.len(),
+    diagnostics.file_errors.len()
+  );
+  Ok(diagnostics)
 }
 
 pub type DependencyCache = HashMap<Location, Module>;
 
-#[tracing::instrument(skip(cache, resolver, module, logger))]
+#[tracing::instrument(skip(cache, resolver, module, diagnostics, logger))]
 pub fn recursively_build_dependency_tree(
   cache: &mut DependencyCache,
   resolver: &Resolver,
   module: Module,
+  diagnostics: &mut Diagnostics,
   logger: &impl ClientSideLogger,
-) -> Result<(), CoreError> {
+) {
   tracing::debug!("Resolving tree for module {:?}", &module.location);
 
   for (index, dependency) in module.dependencies.iter().enumerate() {
@@ -75,7 +96,14 @@ pub fn recursively_build_dependency_tree(
           package_directory,
         } => {
           logger.message(format!("Resolving node module {:?}", &package_directory));
-          let mut module = process_package_json(&resolver, package_directory)?;
+          let module = match process_package_json(&resolver, package_directory, dependency.kind()) {
+            Ok(module) => module,
+            Err(e) => {
+              diagnostics.add_file_error(package_directory, Some(&module.location), true, &e);
+              continue;
+            }
+          };
+          diagnostics.add_unresolved_imports(&module);
           // this has two cache entries one for the dependency itself and one for the package
           tracing::debug!("Inserting: {:?} into {:?}", module.kind, &target_file);
 
@@ -87,7 +115,7 @@ pub fn recursively_build_dependency_tree(
 
           cache.insert(target_file.clone(), module.clone());
 
-          recursively_build_dependency_tree(cache, resolver, module, &*logger);
+          recursively_build_dependency_tree(cache, resolver, module, diagnostics, &*logger);
         }
         Asset::Asset(path) => {
           tracing::debug!("{:?} is an asset. No expansion required", &path)
@@ -95,22 +123,30 @@ pub fn recursively_build_dependency_tree(
         }
         Asset::Module(path) => {
           logger.message(format!("Resolving module {:?}", &path));
-          let next_module = process_javascript_file(&resolver, path)?;
+          let next_module = match process_javascript_file(&resolver, path) {
+            Ok(next_module) => next_module,
+            Err(e) => {
+              diagnostics.add_file_error(path, Some(&module.location), false, &e);
+              continue;
+            }
+          };
+          diagnostics.add_unresolved_imports(&next_module);
           tracing::debug!("Inserting: {:?} into {:?}", module.kind, &path);
           cache.insert(path.clone(), next_module.clone());
-          recursively_build_dependency_tree(cache, resolver, next_module, &*logger);
+          recursively_build_dependency_tree(cache, resolver, next_module, diagnostics, &*logger);
         }
         Asset::Unresolved(path) => {
           tracing::debug!("{:?} could not be resolved", &path)
           // stop. No further expansion here
         }
+        Asset::Ignored(path) => {
+          tracing::debug!("{:?} is ignored by an alias", &path)
+        }
       }
     } else {
       tracing::debug!("{:?} could not be resolved", &dependency);
     }
   }
-
-  Ok(())
 }
 
 #[cfg(test)]
@@ -118,9 +154,12 @@ mod tests {
   use std::collections::HashMap;
   use std::path::{Path, PathBuf};
 
+  use crate::logging::NoopLogger;
   use crate::{build_dependency_cache, start_resolve_project};
 
+  // Needs a checkout of the mathspace repository
   #[test]
+  #[ignore]
   fn the_big_one() {
     let resolver = start_resolve_project(
       Path::new("/home/kevin_velasco/data_disk/Projects/mathspace/mathspace"),
@@ -131,6 +170,6 @@ mod tests {
     let target =
             Path::new("/home/kevin_velasco/data_disk/Projects/mathspace/mathspace/local_modules/ms-pages/Teacher/Teacher.jsx");
     let mut cache = HashMap::new();
-    build_dependency_cache(&resolver, &target, &mut cache);
+    build_dependency_cache(&resolver, &target, &mut cache, &NoopLogger).unwrap();
   }
 }
@@ -1,3 +1,12 @@
 pub trait ClientSideLogger {
   fn message(&self, message: impl AsRef<str>);
 }
+
+/// Drops every message, for tests
+#[cfg(test)]
+pub struct NoopLogger;
+
+#[cfg(test)]
+impl ClientSideLogger for NoopLogger {
+  fn message(&self, _message: impl AsRef<str>) {}
+}
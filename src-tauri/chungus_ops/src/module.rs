@@ -1,4 +1,5 @@
 use crate::error::CoreError;
+use crate::parser::{ImportSpans, ImportedBindings};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
@@ -89,13 +90,13 @@ pub struct RootModule {
   pub dependencies: Vec<Dependency>,
 }
 
-#[derive(Copy, Clone, PartialOrd, PartialEq, Debug, Eq)]
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug, Eq, Serialize, Deserialize)]
 pub enum ModuleKind {
   NodeModule,
   NormalModule,
 }
 
-#[derive(Clone, Debug, PartialOrd, Eq)]
+#[derive(Clone, Debug, PartialOrd, Eq, Serialize, Deserialize)]
 pub struct Module {
   pub location: Location,
   pub kind: ModuleKind,
@@ -114,11 +115,20 @@ impl PartialEq for Module {
   }
 }
 
-#[derive(Clone, PartialOrd, PartialEq, Hash, Eq)]
+#[derive(Clone, PartialOrd, PartialEq, Hash, Eq, Serialize, Deserialize)]
+/// What a module imports, where the import is in its file and what it binds
 pub enum Dependency {
-  Require(Asset),
-  Import(Asset),
-  AsyncImport(Asset),
+  Require(Asset, Option<ImportSpans>, ImportedBindings),
+  Import(Asset, Option<ImportSpans>, ImportedBindings),
+  AsyncImport(Asset, Option<ImportSpans>, ImportedBindings),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+  Import,
+  Require,
+  AsyncImport,
 }
 
 impl Debug for Dependency {
@@ -130,21 +140,42 @@ impl Debug for Dependency {
 impl Dependency {
   pub fn asset(&self) -> &Asset {
     match &self {
-      Dependency::Require(a) => a,
-      Dependency::Import(a) => a,
-      Dependency::AsyncImport(a) => a,
+      Dependency::Require(a, ..) => a,
+      Dependency::Import(a, ..) => a,
+      Dependency::AsyncImport(a, ..) => a,
+    }
+  }
+  pub fn spans(&self) -> Option<ImportSpans> {
+    match &self {
+      Dependency::Require(_, spans, _) => *spans,
+      Dependency::Import(_, spans, _) => *spans,
+      Dependency::AsyncImport(_, spans, _) => *spans,
+    }
+  }
+  pub fn bindings(&self) -> &ImportedBindings {
+    match &self {
+      Dependency::Require(.., bindings) => bindings,
+      Dependency::Import(.., bindings) => bindings,
+      Dependency::AsyncImport(.., bindings) => bindings,
+    }
+  }
+  pub fn kind(&self) -> DependencyKind {
+    match &self {
+      Dependency::Require(..) => DependencyKind::Require,
+      Dependency::Import(..) => DependencyKind::Import,
+      Dependency::AsyncImport(..) => DependencyKind::AsyncImport,
     }
   }
   pub fn location(&self) -> Option<Location> {
     match &self {
-      Dependency::Require(loc) => loc.location(),
-      Dependency::Import(loc) => loc.location(),
-      Dependency::AsyncImport(loc) => loc.location(),
+      Dependency::Require(loc, ..) => loc.location(),
+      Dependency::Import(loc, ..) => loc.location(),
+      Dependency::AsyncImport(loc, ..) => loc.location(),
     }
   }
 }
 
-#[derive(Debug, Clone, PartialOrd, PartialEq, Hash, Eq)]
+#[derive(Debug, Clone, PartialOrd, PartialEq, Hash, Eq, Serialize, Deserialize)]
 pub enum Asset {
   NodePackage {
     package_directory: Location,
@@ -153,6 +184,8 @@ pub enum Asset {
   Asset(Location),
   Module(Location),
   Unresolved(PathBuf),
+  // aliased to `false` in the webpack configuration
+  Ignored(PathBuf),
 }
 
 impl Asset {
@@ -162,6 +195,7 @@ impl Asset {
       Asset::Asset(path) => Some(path.clone()),
       Asset::Module(path) => Some(path.clone()),
       Asset::Unresolved(_) => None,
+      Asset::Ignored(_) => None,
     }
   }
 }
@@ -0,0 +1,292 @@
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+// Node's resolution algorithm for the package.json `exports` and `imports` fields
+// https://nodejs.org/api/esm.html#resolution-algorithm-specification
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportsTarget {
+  /// A file inside the package
+  Path(PathBuf),
+  /// `imports` can also map to another package, like `"#dep": "dep-node-native"`
+  Package(String),
+}
+
+/// Splits a bare specifier into the package name and the subpath inside the package.
+/// `@scope/pkg/feature` becomes `("@scope/pkg", "./feature")` and `react` becomes
+/// `("react", ".")`.
+pub fn split_package_specifier(specifier: &str) -> Option<(&str, String)> {
+  if specifier.is_empty() || specifier.starts_with('.') || specifier.starts_with('/') {
+    return None;
+  }
+
+  let mut separators = specifier.match_indices('/').map(|(index, _)| index);
+  let name_end = if specifier.starts_with('@') {
+    // a scope on its own isn't a package
+    separators
+      .nth(1)
+      .or_else(|| Some(specifier.len()).filter(|_| specifier.contains('/')))?
+  } else {
+    separators.next().unwrap_or(specifier.len())
+  };
+
+  Some((
+    &specifier[..name_end],
+    format!(".{}", &specifier[name_end..]),
+  ))
+}
+
+/// Resolves `subpath` (`"."` or `"./feature"`) through a package's `exports`. `None` means the
+/// subpath isn't exported.
+pub fn resolve_exports(
+  package_directory: &Path,
+  exports: &Value,
+  subpath: &str,
+  conditions: &[&str],
+) -> Option<PathBuf> {
+  let subpath_map = exports
+    .as_object()
+    .filter(|map| map.keys().any(|key| key.starts_with('.')));
+
+  let target = match subpath_map {
+    Some(map) => resolve_map(package_directory, map, subpath, conditions, false),
+    // `"exports": "./index.js"` and condition objects only export the package root
+    None if subpath == "." => resolve_target(package_directory, exports, None, conditions, false),
+    None => None,
+  };
+
+  match target? {
+    ExportsTarget::Path(path) => Some(path),
+    ExportsTarget::Package(_) => None,
+  }
+}
+
+/// Resolves a `#specifier` through the `imports` of the package it is imported from
+pub fn resolve_imports(
+  package_directory: &Path,
+  imports: &Value,
+  specifier: &str,
+  conditions: &[&str],
+) -> Option<ExportsTarget> {
+  resolve_map(
+    package_directory,
+    imports.as_object()?,
+    specifier,
+    conditions,
+    true,
+  )
+}
+
+fn resolve_map(
+  package_directory: &Path,
+  map: &Map<String, Value>,
+  key: &str,
+  conditions: &[&str],
+  allow_packages: bool,
+) -> Option<ExportsTarget> {
+  if let Some(target) = map.get(key).filter(|_| !key.contains('*')) {
+    return resolve_target(package_directory, target, None, conditions, allow_packages);
+  }
+
+  // the pattern with the longest prefix wins, then the longest pattern
+  let mut best_match: Option<(&str, &Value, &str)> = None;
+  for (pattern, target) in map.iter() {
+    let star = match pattern.find('*') {
+      Some(star) => star,
+      None => continue,
+    };
+    let (prefix, suffix) = (&pattern[..star], &pattern[star + 1..]);
+
+    let matches = key.starts_with(prefix)
+      && key != prefix
+      && key.len() >= pattern.len()
+      && key.ends_with(suffix);
+    let is_better = best_match
+      .map(|(best, _, _)| {
+        let best_prefix = best.find('*').unwrap_or(best.len());
+        star > best_prefix || (star == best_prefix && pattern.len() > best.len())
+      })
+      .unwrap_or(true);
+
+    if matches && is_better {
+      best_match = Some((
+        pattern,
+        target,
+        &key[prefix.len()..key.len() - suffix.len()],
+      ));
+    }
+  }
+
+  let (_, target, wildcard) = best_match?;
+  resolve_target(
+    package_directory,
+    target,
+    Some(wildcard),
+    conditions,
+    allow_packages,
+  )
+}
+
+fn resolve_target(
+  package_directory: &Path,
+  target: &Value,
+  wildcard: Option<&str>,
+  conditions: &[&str],
+  allow_packages: bool,
+) -> Option<ExportsTarget> {
+  match target {
+    Value::String(target) => {
+      let target = match wildcard {
+        Some(wildcard) => target.replace('*', wildcard),
+        None => target.clone(),
+      };
+
+      if let Some(relative) = target.strip_prefix("./") {
+        Some(ExportsTarget::Path(package_directory.join(relative)))
+      } else if allow_packages && !target.starts_with('/') && !target.starts_with("../") {
+        Some(ExportsTarget::Package(target))
+      } else {
+        None
+      }
+    }
+    // fallbacks, the first one that resolves is used
+    Value::Array(targets) => targets.iter().find_map(|target| {
+      resolve_target(
+        package_directory,
+        target,
+        wildcard,
+        conditions,
+        allow_packages,
+      )
+    }),
+    // conditions are matched in the order they are written in, not the order we prefer them in
+    Value::Object(conditional_targets) => conditional_targets
+      .iter()
+      .filter(|(condition, _)| *condition == "default" || conditions.contains(&condition.as_str()))
+      .find_map(|(_, target)| {
+        resolve_target(
+          package_directory,
+          target,
+          wildcard,
+          conditions,
+          allow_packages,
+        )
+      }),
+    // null means the path is explicitly not exported
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn it_splits_package_specifiers() {
+    assert_eq!(
+      split_package_specifier("react"),
+      Some(("react", ".".into()))
+    );
+    assert_eq!(
+      split_package_specifier("lodash-es/fp"),
+      Some(("lodash-es", "./fp".into()))
+    );
+    assert_eq!(
+      split_package_specifier("@scope/pkg/feature/deep"),
+      Some(("@scope/pkg", "./feature/deep".into()))
+    );
+    assert_eq!(
+      split_package_specifier("@scope/pkg"),
+      Some(("@scope/pkg", ".".into()))
+    );
+    assert_eq!(split_package_specifier("@scope"), None);
+    assert_eq!(split_package_specifier("./relative"), None);
+  }
+
+  #[test]
+  fn it_resolves_exports_with_conditions_and_patterns() {
+    let directory = Path::new("/pkg");
+    let exports = json!({
+      ".": {
+        "types": "./index.d.ts",
+        "import": { "browser": "./esm/browser.mjs", "default": "./esm/index.mjs" },
+        "require": "./cjs/index.js"
+      },
+      "./feature": ["not-a-relative-path", "./feature/index.js"],
+      "./features/*": "./src/features/*.js",
+      "./features/private/*": null,
+      "./package.json": "./package.json"
+    });
+
+    assert_eq!(
+      resolve_exports(directory, &exports, ".", &["import", "browser"]),
+      Some(PathBuf::from("/pkg/esm/browser.mjs"))
+    );
+    assert_eq!(
+      resolve_exports(directory, &exports, ".", &["import"]),
+      Some(PathBuf::from("/pkg/esm/index.mjs"))
+    );
+    assert_eq!(
+      resolve_exports(directory, &exports, ".", &["require"]),
+      Some(PathBuf::from("/pkg/cjs/index.js"))
+    );
+    assert_eq!(
+      resolve_exports(directory, &exports, "./feature", &[]),
+      Some(PathBuf::from("/pkg/feature/index.js"))
+    );
+    assert_eq!(
+      resolve_exports(directory, &exports, "./features/a/b", &[]),
+      Some(PathBuf::from("/pkg/src/features/a/b.js"))
+    );
+    assert_eq!(
+      resolve_exports(directory, &exports, "./features/private/a", &[]),
+      None
+    );
+    assert_eq!(resolve_exports(directory, &exports, "./other", &[]), None);
+  }
+
+  #[test]
+  fn it_treats_sugar_as_the_package_root() {
+    let directory = Path::new("/pkg");
+    assert_eq!(
+      resolve_exports(directory, &json!("./main.js"), ".", &[]),
+      Some(PathBuf::from("/pkg/main.js"))
+    );
+    assert_eq!(
+      resolve_exports(
+        directory,
+        &json!({ "node": "./node.js", "default": "./web.js" }),
+        ".",
+        &["browser"]
+      ),
+      Some(PathBuf::from("/pkg/web.js"))
+    );
+    assert_eq!(
+      resolve_exports(directory, &json!("./main.js"), "./sub", &[]),
+      None
+    );
+  }
+
+  #[test]
+  fn it_resolves_imports() {
+    let directory = Path::new("/pkg");
+    let imports = json!({
+      "#internal/*": "./src/internal/*.js",
+      "#dep": { "node": "dep-node-native", "default": "./dep-polyfill.js" }
+    });
+
+    assert_eq!(
+      resolve_imports(directory, &imports, "#internal/a", &[]),
+      Some(ExportsTarget::Path(PathBuf::from("/pkg/src/internal/a.js")))
+    );
+    assert_eq!(
+      resolve_imports(directory, &imports, "#dep", &["node"]),
+      Some(ExportsTarget::Package("dep-node-native".into()))
+    );
+    assert_eq!(
+      resolve_imports(directory, &imports, "#dep", &["browser"]),
+      Some(ExportsTarget::Path(PathBuf::from("/pkg/dep-polyfill.js")))
+    );
+  }
+}
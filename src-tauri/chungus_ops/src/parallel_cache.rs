@@ -0,0 +1,214 @@
+use dashmap::{DashMap, DashSet};
+use parking_lot::Mutex;
+
+use crate::diagnostics::Diagnostics;
+use crate::error::CoreError;
+use crate::file::{process_javascript_file, process_package_json};
+use crate::logging::ClientSideLogger;
+use crate::module::{Asset, Dependency, Location, Module};
+use crate::resolve::Resolver;
+use crate::DependencyCache;
+
+// Everything the workers share while the cache is being built
+struct SharedCache<'a, L> {
+  resolver: &'a Resolver,
+  logger: &'a L,
+  // what was already in the cache before this build, it isn't touched
+  existing: &'a DependencyCache,
+  modules: DashMap<Location, Module>,
+  // locations a worker has been given, so every file is only read once
+  claimed: DashSet<Location>,
+  diagnostics: Mutex<Diagnostics>,
+}
+
+/// `build_dependency_cache` spread over `threads` threads. Every module that is found is queued
+/// on a thread pool and read, parsed and resolved there, so the cache comes out the same as the
+/// one built one file at a time. `threads` of 0 uses one thread per cpu.
+///
+/// The entrypoint has to be readable. Any other file that can't be read is left out of the cache
+/// and reported in the diagnostics.
+#[tracing::instrument(skip(resolver, target, cache, logger))]
+pub fn build_dependency_cache_parallel(
+  resolver: &Resolver,
+  target: &Location,
+  cache: &mut DependencyCache,
+  threads: usize,
+  logger: &(impl ClientSideLogger + Sync),
+) -> Result<Diagnostics, CoreError> {
+  tracing::info!(
+    "Start parallel build dependency cache {:?} on {} threads",
+    target,
+    threads
+  );
+
+  let root_module = process_javascript_file(resolver, target)?;
+  logger.message("Loaded root module");
+  let mut diagnostics = Diagnostics::default();
+  diagnostics.add_unresolved_imports(&root_module);
+
+  let pool = rayon::ThreadPoolBuilder::new()
+    .num_threads(threads)
+    .build()
+    .map_err(|e| CoreError::custom(&format!("Could not start the thread pool: {}", e)))?;
+
+  let shared = SharedCache {
+    resolver,
+    logger,
+    existing: cache,
+    modules: DashMap::new(),
+    claimed: DashSet::new(),
+    diagnostics: Mutex::new(diagnostics),
+  };
+  shared.claimed.insert(target.clone());
+  shared.modules.insert(target.clone(), root_module.clone());
+
+  pool.scope(|scope| queue_dependencies(scope, &shared, &root_module));
+
+  let SharedCache {
+    modules,
+    diagnostics,
+    ..
+  } = shared;
+  cache.extend(modules.into_iter());
+  let mut diagnostics = diagnostics.into_inner();
+  diagnostics.sort();
+
+  tracing::info!("Built dependency cache {:?}", target);
+  Ok(diagnostics)
+}
+
+fn queue_dependencies<'scope, L: ClientSideLogger + Sync>(
+  scope: &rayon::Scope<'scope>,
+  shared: &'scope SharedCache<'scope, L>,
+  module: &Module,
+) {
+  for dependency in module.dependencies.iter() {
+    let location = match dependency.location() {
+      Some(location) => location,
+      None => {
+        tracing::debug!("{:?} could not be resolved", dependency);
+        continue;
+      }
+    };
+
+    if shared.existing.contains_key(&location) || !shared.claimed.insert(location) {
+      continue;
+    }
+
+    let dependency = dependency.clone();
+    let importer = module.location.clone();
+    scope.spawn(move |scope| {
+      if let Some(module) = load_dependency(shared, &importer, &dependency) {
+        queue_dependencies(scope, shared, &module)
+      }
+    });
+  }
+}
+
+// Reads a dependency into the cache, the same way `recursively_build_dependency_tree` does.
+// Returns the module whose dependencies still have to be loaded.
+fn load_dependency<L: ClientSideLogger>(
+  shared: &SharedCache<L>,
+  importer: &Location,
+  dependency: &Dependency,
+) -> Option<Module> {
+  let (file, is_package_json, loaded) = match dependency.asset() {
+    Asset::NodePackage {
+      package_directory, ..
+    } => {
+      shared
+        .logger
+        .message(format!("Resolving node module {:?}", &package_directory));
+      let loaded = process_package_json(shared.resolver, package_directory, dependency.kind());
+      (package_directory, true, loaded)
+    }
+    Asset::Module(path) => {
+      shared
+        .logger
+        .message(format!("Resolving module {:?}", &path));
+      (path, false, process_javascript_file(shared.resolver, path))
+    }
+    Asset::Asset(_) | Asset::Unresolved(_) | Asset::Ignored(_) => return None,
+  };
+
+  let module = match loaded {
+    Ok(module) => module,
+    Err(e) => {
+      let mut diagnostics = shared.diagnostics.lock();
+      diagnostics.add_file_error(file, Some(importer), is_package_json, &e);
+      return None;
+    }
+  };
+  // lines are looked up before taking the lock
+  let mut found = Diagnostics::default();
+  found.add_unresolved_imports(&module);
+  shared
+    .diagnostics
+    .lock()
+    .unresolved_imports
+    .extend(found.unresolved_imports);
+
+  if let Asset::NodePackage {
+    target_file,
+    package_directory,
+  } = dependency.asset()
+  {
+    if target_file != package_directory {
+      shared
+        .modules
+        .insert(package_directory.clone(), module.clone());
+    }
+    shared.modules.insert(target_file.clone(), module.clone());
+  } else {
+    shared.modules.insert(file.clone(), module.clone());
+  }
+  Some(module)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::build_dependency_cache;
+  use crate::logging::NoopLogger;
+  use std::path::Path;
+
+  #[test]
+  fn it_builds_the_same_cache_as_the_sequential_build() {
+    for (fixture, entrypoint, recursively_resolve_node_modules) in [
+      ("sample_javascript", "module/a.js", false),
+      ("sample_cycles", "index.js", false),
+      ("sample_duplicates", "index.js", true),
+    ]
+    .iter()
+    {
+      let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src")
+        .join(fixture);
+      let mut resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+      resolver.recursively_resolve_node_modules = *recursively_resolve_node_modules;
+      let entrypoint = Location::new(root.join(entrypoint)).unwrap();
+
+      let mut sequential = DependencyCache::new();
+      build_dependency_cache(&resolver, &entrypoint, &mut sequential, &NoopLogger).unwrap();
+
+      for threads in [1, 4].iter() {
+        let mut parallel = DependencyCache::new();
+        build_dependency_cache_parallel(
+          &resolver,
+          &entrypoint,
+          &mut parallel,
+          *threads,
+          &NoopLogger,
+        )
+        .unwrap();
+
+        assert_eq!(parallel.len(), sequential.len());
+        for (location, module) in sequential.iter() {
+          let other = &parallel[location];
+          assert_eq!(other.location, module.location);
+          assert_eq!(other.dependencies, module.dependencies);
+        }
+      }
+    }
+  }
+}
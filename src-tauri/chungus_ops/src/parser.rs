@@ -1,8 +1,12 @@
-use crate::parser::parsers::all_possible_import_types;
+use crate::parser::lexer::CodeScanner;
+use crate::parser::parsers::{all_possible_import_types, ParsedImport};
+use nom::Offset;
+use serde::{Deserialize, Serialize};
 use std::any::TypeId;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
+mod lexer;
 mod parsers;
 
 #[derive(Debug)]
@@ -11,8 +15,10 @@ pub enum ParseError {
 }
 
 impl Display for ParseError {
-    fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
-        todo!()
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Unknown(e) => write!(f, "Could not parse imports: {}", e),
+        }
     }
 }
 
@@ -33,6 +39,20 @@ pub enum Import {
     NodeDependency(PathBuf),
 }
 
+impl Import {
+    /// The same kind of import, for a different specifier
+    pub fn with_path(&self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        match self {
+            Import::Require(_) => Import::Require(path),
+            Import::AsyncImport(_) => Import::AsyncImport(path),
+            Import::ExportFrom(_) => Import::ExportFrom(path),
+            Import::Import(_) => Import::Import(path),
+            Import::NodeDependency(_) => Import::NodeDependency(path),
+        }
+    }
+}
+
 impl AsRef<Path> for Import {
     fn as_ref(&self) -> &Path {
         match self {
@@ -45,49 +65,244 @@ impl AsRef<Path> for Import {
     }
 }
 
+/// How `UnresolvedImport::parse_many_with_mode` looks for imports in a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanMode {
+    /// Tries to parse an import at every offset of the file. This also finds imports in comments,
+    /// strings and jsx text.
+    Permissive,
+    /// Only tries to parse imports in code, comments and string, template and regex literals are
+    /// skipped. With `jsx` turned off a `<` never opens a jsx element, which is what typescript
+    /// files need as `<Type>value` is a type assertion there.
+    Lexical { jsx: bool },
+}
+
+impl Default for ScanMode {
+    fn default() -> Self {
+        ScanMode::Lexical { jsx: true }
+    }
+}
+
+impl ScanMode {
+    pub fn for_path(path: impl AsRef<Path>) -> Self {
+        let extension = path.as_ref().extension().unwrap_or_default();
+        match &*extension.to_string_lossy() {
+            "ts" | "mts" | "cts" => ScanMode::Lexical { jsx: false },
+            _ => ScanMode::default(),
+        }
+    }
+}
+
+/// A position in a file. Lines and columns start at 1, columns count characters rather than bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SourcePosition {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// From `start` up to but not including `end`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SourceSpan {
+    pub start: SourcePosition,
+    pub end: SourcePosition,
+}
+
+/// Where an import was declared in the file it was parsed from
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ImportSpans {
+    /// From the `import`, `export` or `require` keyword to the end of the specifier, or the closing
+    /// parenthesis of a call
+    pub statement: SourceSpan,
+    /// The specifier string, quotes included
+    pub specifier: SourceSpan,
+}
+
+/// What an import makes available to the module it is in
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportedBindings {
+    /// `require()`, `import()` and package.json dependencies, any export could be used
+    #[default]
+    Unknown,
+    /// `import "./polyfill"`, the module only runs for its side effects
+    SideEffect,
+    /// `import a, * as b from` or `import a, { c, d as e } from`
+    Import {
+        default: Option<String>,
+        namespace: Option<String>,
+        named: Vec<NamedBinding>,
+    },
+    /// `export * from`, every export except the default one is exported again
+    ExportAll,
+    /// `export * as a from`
+    ExportNamespace(String),
+    /// `export { a, b as c } from`
+    ExportNamed(Vec<NamedBinding>),
+}
+
+/// `a` or `a as b` in an import or export list
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct NamedBinding {
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+// Turns byte offsets into lines and columns
+struct LineIndex<'a> {
+    contents: &'a str,
+    // the offset every line starts at
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(contents: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(contents.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Self {
+            contents,
+            line_starts,
+        }
+    }
+
+    fn position(&self, offset: usize) -> SourcePosition {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+        SourcePosition {
+            offset,
+            line: line + 1,
+            column: self.contents[line_start..offset].chars().count() + 1,
+        }
+    }
+
+    fn span(&self, start: usize, end: usize) -> SourceSpan {
+        SourceSpan {
+            start: self.position(start),
+            end: self.position(end),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
-#[repr(transparent)]
-pub struct UnresolvedImport(pub Import);
+pub struct UnresolvedImport {
+    pub import: Import,
+    /// Imports that weren't parsed from source, like the dependencies of a package.json, have none
+    pub spans: Option<ImportSpans>,
+    pub bindings: ImportedBindings,
+}
+
+impl From<Import> for UnresolvedImport {
+    fn from(import: Import) -> Self {
+        Self {
+            import,
+            spans: None,
+            bindings: ImportedBindings::Unknown,
+        }
+    }
+}
 
 #[cfg(test)]
 impl UnresolvedImport {
     pub fn new_from_import(import: Import) -> Self {
-        Self(import)
+        import.into()
     }
 }
 
 impl AsRef<Path> for UnresolvedImport {
     fn as_ref(&self) -> &Path {
-        self.0.as_ref()
+        self.import.as_ref()
     }
 }
 
 impl UnresolvedImport {
     pub fn import_kind(&self) -> &Import {
-        &self.0
+        &self.import
+    }
+
+    /// The same import at the same place, for a different specifier
+    pub fn with_path(&self, path: impl Into<PathBuf>) -> Self {
+        Self {
+            import: self.import.with_path(path),
+            spans: self.spans,
+            bindings: self.bindings.clone(),
+        }
     }
+
     pub fn parse_many(module_contents: impl AsRef<str>) -> Result<Vec<Self>, ParseError> {
-        let mut contents = module_contents.as_ref().to_owned();
+        Self::parse_many_with_mode(module_contents, ScanMode::default())
+    }
+
+    pub fn parse_many_with_mode(
+        module_contents: impl AsRef<str>,
+        mode: ScanMode,
+    ) -> Result<Vec<Self>, ParseError> {
+        match mode {
+            ScanMode::Permissive => Self::parse_many_permissive(module_contents.as_ref()),
+            ScanMode::Lexical { jsx } => {
+                Ok(Self::parse_many_lexical(module_contents.as_ref(), jsx))
+            }
+        }
+    }
+
+    fn parse_many_lexical(contents: &str, jsx: bool) -> Vec<Self> {
+        let lines = LineIndex::new(contents);
+        let mut output = vec![];
+        let mut scanner = CodeScanner::new(contents, jsx);
+
+        while let Some(offset) = scanner.next() {
+            if let Ok((remaining, parsed)) = all_possible_import_types(&contents[offset..]) {
+                let end = contents.len() - remaining.len();
+                output.push(Self::parsed(&lines, parsed, offset, end));
+                scanner.skip_to(end);
+            }
+        }
+
+        output
+    }
+
+    fn parse_many_permissive(contents: &str) -> Result<Vec<Self>, ParseError> {
+        let lines = LineIndex::new(contents);
+        let mut remaining = contents;
         let mut output = vec![];
 
         loop {
-            let result = all_possible_import_types(&contents);
-            match result {
-                Ok((remaining, out)) => {
-                    output.push(UnresolvedImport(out));
-                    contents = remaining.to_owned();
+            match all_possible_import_types(remaining) {
+                Ok((next, parsed)) => {
+                    let offset = contents.offset(remaining);
+                    let end = contents.offset(next);
+                    output.push(Self::parsed(&lines, parsed, offset, end));
+                    remaining = next;
                 }
                 Err(_) => {
-                    if contents.is_empty() {
+                    let mut chars = remaining.chars();
+                    if chars.next().is_none() {
                         break;
                     }
-                    contents.remove(0);
+                    remaining = chars.as_str();
                 }
             }
         }
 
         Ok(output)
     }
+
+    // The statement from `offset` to `end` was parsed into `parsed`
+    fn parsed(lines: &LineIndex, parsed: ParsedImport, offset: usize, end: usize) -> Self {
+        let (import, specifier, bindings) = parsed;
+        let specifier_start = lines.contents.offset(specifier);
+        Self {
+            import,
+            spans: Some(ImportSpans {
+                statement: lines.span(offset, end),
+                specifier: lines.span(specifier_start, specifier_start + specifier.len()),
+            }),
+            bindings,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -109,18 +324,18 @@ mod tests {
 
         let output = UnresolvedImport::parse_many(source);
         assert!(output.is_ok(), "{:?}", &output);
-        let output = output.unwrap();
+        let output: Vec<_> = output.unwrap().into_iter().map(|i| i.import).collect();
 
         use super::Import::*;
 
         assert_eq!(
             output,
             vec![
-                UnresolvedImport(Import("ramda".into())),
-                UnresolvedImport(Import("gallileo".into())),
-                UnresolvedImport(Import("bazooka".into())),
-                UnresolvedImport(Import("./components/component".into())),
-                UnresolvedImport(ExportFrom("./local".into()))
+                Import("ramda".into()),
+                Import("gallileo".into()),
+                Import("bazooka".into()),
+                Import("./components/component".into()),
+                ExportFrom("./local".into())
             ]
         )
     }
@@ -142,17 +357,153 @@ mod tests {
 
         let output = UnresolvedImport::parse_many(source);
         assert!(output.is_ok(), "{:?}", &output);
-        let output = output.unwrap();
+        let output: Vec<_> = output.unwrap().into_iter().map(|i| i.import).collect();
         use super::Import::*;
         assert_eq!(
             output,
             vec![
-                UnresolvedImport(Import("ramda".into())),
-                UnresolvedImport(Import("gallileo".into())),
-                UnresolvedImport(Import("bazooka".into())),
-                UnresolvedImport(Import("./components/component".into())),
-                UnresolvedImport(AsyncImport("./async/Component".into()))
+                Import("ramda".into()),
+                Import("gallileo".into()),
+                Import("bazooka".into()),
+                Import("./components/component".into()),
+                AsyncImport("./async/Component".into())
             ]
         )
     }
+
+    #[test]
+    fn it_ignores_imports_that_are_not_code() {
+        let source = r#"
+            // import a from "commented";
+            /*
+             * require("block-commented")
+             */
+            import b from "b";
+            const c = "require('string')";
+            const d = `import("template") ${require("d")}`;
+            const e = /require\("regex"\)/;
+            const f = reimport("identifier");
+            const g = () => <p>Use require("jsx-text") here</p>;
+        "#;
+
+        use super::Import::*;
+        let output = UnresolvedImport::parse_many(source).unwrap();
+        assert_eq!(
+            output.into_iter().map(|i| i.import).collect::<Vec<_>>(),
+            vec![
+                Import("b".into()),
+                Require("d".into()),
+            ]
+        );
+
+        let permissive = UnresolvedImport::parse_many_with_mode(source, ScanMode::Permissive);
+        assert_eq!(permissive.unwrap().len(), 8);
+    }
+
+    #[test]
+    fn it_finds_side_effect_imports_and_export_froms() {
+        let source = r#"
+            import "./styles.css";
+            import './setup';
+            const label = "imported from elsewhere";
+            export {
+              default
+            } from './x';
+            import x, { y } from "./y";
+        "#;
+
+        use super::Import::*;
+        let output = UnresolvedImport::parse_many(source).unwrap();
+        assert_eq!(
+            output
+                .iter()
+                .map(|i| (i.import.clone(), i.bindings.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Import("./styles.css".into()), ImportedBindings::SideEffect),
+                (Import("./setup".into()), ImportedBindings::SideEffect),
+                (
+                    ExportFrom("./x".into()),
+                    ImportedBindings::ExportNamed(vec![NamedBinding {
+                        name: "default".to_string(),
+                        alias: None,
+                    }])
+                ),
+                (
+                    Import("./y".into()),
+                    ImportedBindings::Import {
+                        default: Some("x".to_string()),
+                        namespace: None,
+                        named: vec![NamedBinding {
+                            name: "y".to_string(),
+                            alias: None,
+                        }],
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_records_where_imports_are() {
+        let source = "const é = 1;\nimport {\n  a\n} from \"./a\";\nconst é = require('b');\n";
+
+        let spans: Vec<ImportSpans> = UnresolvedImport::parse_many(source)
+            .unwrap()
+            .into_iter()
+            .map(|import| import.spans.unwrap())
+            .collect();
+        let position = |offset, line, column| SourcePosition {
+            offset,
+            line,
+            column,
+        };
+        assert_eq!(
+            spans,
+            vec![
+                ImportSpans {
+                    statement: SourceSpan {
+                        start: position(14, 2, 1),
+                        end: position(39, 4, 13),
+                    },
+                    specifier: SourceSpan {
+                        start: position(34, 4, 8),
+                        end: position(39, 4, 13),
+                    },
+                },
+                ImportSpans {
+                    statement: SourceSpan {
+                        start: position(52, 5, 11),
+                        end: position(64, 5, 23),
+                    },
+                    specifier: SourceSpan {
+                        start: position(60, 5, 19),
+                        end: position(63, 5, 22),
+                    },
+                },
+            ]
+        );
+        assert_eq!(&source[34..39], "\"./a\"");
+        assert_eq!(&source[60..63], "'b'");
+
+        let permissive =
+            UnresolvedImport::parse_many_with_mode(source, ScanMode::Permissive).unwrap();
+        assert_eq!(
+            permissive.into_iter().map(|i| i.spans.unwrap()).collect::<Vec<_>>(),
+            spans
+        );
+    }
+
+    #[test]
+    fn it_picks_a_scan_mode_from_the_extension() {
+        assert_eq!(
+            ScanMode::for_path("a/b.ts"),
+            ScanMode::Lexical { jsx: false }
+        );
+        assert_eq!(
+            ScanMode::for_path("a/b.tsx"),
+            ScanMode::Lexical { jsx: true }
+        );
+        assert_eq!(ScanMode::for_path("a/b.js"), ScanMode::Lexical { jsx: true });
+    }
 }
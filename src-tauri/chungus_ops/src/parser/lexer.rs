@@ -0,0 +1,471 @@
+// A very small javascript lexer. It doesn't produce tokens, it only knows enough of the grammar to
+// tell code apart from comments, string / template / regex literals and jsx text so that import
+// detection only ever looks at code.
+
+/// Where in the source the scanner currently is, the top of the stack decides how the next byte is
+/// interpreted. An empty stack is plain code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Context {
+  /// The text portion of a template literal
+  Template,
+  /// Code inside `${ }`, the number is how many unclosed `{` there are inside the expression
+  TemplateExpression(usize),
+  /// Between `<Name` and `>` / `/>`
+  JsxOpeningTag,
+  /// Between `</` and `>`
+  JsxClosingTag,
+  /// The text between an opening and closing jsx tag
+  JsxChildren,
+  /// Code inside `{ }` in a jsx tag or in jsx children
+  JsxExpression(usize),
+}
+
+const KEYWORDS_BEFORE_EXPRESSION: [&str; 14] = [
+  "return",
+  "typeof",
+  "instanceof",
+  "in",
+  "of",
+  "new",
+  "delete",
+  "void",
+  "throw",
+  "case",
+  "do",
+  "else",
+  "yield",
+  "await",
+];
+
+const IMPORT_KEYWORDS: [&str; 3] = ["import", "export", "require"];
+
+/// Walks javascript source and yields the byte offset of every `import`, `export` and `require`
+/// that appears as a whole word in code.
+pub struct CodeScanner<'a> {
+  source: &'a [u8],
+  position: usize,
+  contexts: Vec<Context>,
+  jsx: bool,
+  // whether a `/` at this point starts a regex literal rather than being a division. This is
+  // also where an expression can start, so it doubles as the check for `<` opening jsx
+  expression_allowed: bool,
+  after_dot: bool,
+}
+
+fn is_identifier_start(byte: u8) -> bool {
+  byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$' || byte == b'\\' || byte >= 0x80
+}
+
+fn is_identifier_part(byte: u8) -> bool {
+  is_identifier_start(byte) || byte.is_ascii_digit()
+}
+
+impl<'a> CodeScanner<'a> {
+  pub fn new(source: &'a str, jsx: bool) -> Self {
+    Self {
+      source: source.as_bytes(),
+      position: 0,
+      contexts: vec![],
+      jsx,
+      expression_allowed: true,
+      after_dot: false,
+    }
+  }
+
+  /// Continue scanning from `offset`. Used after a statement starting at a yielded offset has been
+  /// consumed, everything the statements end with (a string or a `)`) is followed by an operator.
+  pub fn skip_to(&mut self, offset: usize) {
+    self.position = offset.max(self.position);
+    self.expression_allowed = false;
+    self.after_dot = false;
+  }
+
+  fn peek(&self, offset: usize) -> Option<u8> {
+    self.source.get(self.position + offset).copied()
+  }
+
+  fn skip_until(&mut self, needle: &[u8]) {
+    while self.position < self.source.len() {
+      if self.source[self.position..].starts_with(needle) {
+        self.position += needle.len();
+        return;
+      }
+      self.position += 1;
+    }
+  }
+
+  fn skip_string(&mut self, quote: u8) {
+    // opening quote
+    self.position += 1;
+    while let Some(byte) = self.peek(0) {
+      self.position += 1;
+      match byte {
+        b'\\' => self.position += 1,
+        b'\n' if quote != b'`' => return,
+        byte if byte == quote => return,
+        _ => {}
+      }
+    }
+  }
+
+  /// Skips a regex literal if there is one at the current position. A `/` that doesn't close on
+  /// the same line was a division after all.
+  fn skip_regex(&mut self) -> bool {
+    let mut offset = 1;
+    let mut in_class = false;
+    loop {
+      match self.peek(offset) {
+        None | Some(b'\n') => return false,
+        Some(b'\\') => offset += 1,
+        Some(b'[') => in_class = true,
+        Some(b']') => in_class = false,
+        Some(b'/') if !in_class => break,
+        _ => {}
+      }
+      offset += 1;
+    }
+    self.position += offset + 1;
+    // flags
+    while self.peek(0).map(is_identifier_part).unwrap_or(false) {
+      self.position += 1;
+    }
+    true
+  }
+
+  fn read_word(&mut self) -> &'a str {
+    let start = self.position;
+    while self.peek(0).map(is_identifier_part).unwrap_or(false) {
+      self.position += 1;
+    }
+    std::str::from_utf8(&self.source[start..self.position]).unwrap_or_default()
+  }
+
+  /// Typescript generics on arrow functions look like a jsx tag, `<T,>` and `<T extends U>` are
+  /// how they are told apart in .tsx files.
+  fn looks_like_jsx(&self) -> bool {
+    match self.peek(1) {
+      Some(b'>') => return true,
+      Some(byte) if is_identifier_start(byte) => {}
+      _ => return false,
+    }
+
+    let mut offset = 1;
+    while self.peek(offset).map(is_identifier_part).unwrap_or(false) {
+      offset += 1;
+    }
+    while self
+      .peek(offset)
+      .map(|b| b.is_ascii_whitespace())
+      .unwrap_or(false)
+    {
+      offset += 1;
+    }
+
+    let rest = &self.source[(self.position + offset).min(self.source.len())..];
+    !(rest.starts_with(b",") || rest.starts_with(b"extends "))
+  }
+
+  /// Handles one byte (or a run of bytes) of code. Returns the offset of the word if an import
+  /// keyword was passed.
+  fn step_code(&mut self) -> Option<usize> {
+    let byte = self.peek(0)?;
+    let after_dot = std::mem::replace(&mut self.after_dot, false);
+
+    match byte {
+      byte if byte.is_ascii_whitespace() => {
+        self.position += 1;
+        self.after_dot = after_dot;
+      }
+      b'/' if self.peek(1) == Some(b'/') => {
+        self.skip_until(b"\n");
+        self.after_dot = after_dot;
+      }
+      b'/' if self.peek(1) == Some(b'*') => {
+        self.position += 2;
+        self.skip_until(b"*/");
+        self.after_dot = after_dot;
+      }
+      b'/' => {
+        if !(self.expression_allowed && self.skip_regex()) {
+          self.position += 1;
+          self.expression_allowed = true;
+        } else {
+          self.expression_allowed = false;
+        }
+      }
+      b'\'' | b'"' => {
+        self.skip_string(byte);
+        self.expression_allowed = false;
+      }
+      b'`' => {
+        self.position += 1;
+        self.contexts.push(Context::Template);
+      }
+      b'{' => {
+        self.position += 1;
+        self.expression_allowed = true;
+        match self.contexts.last_mut() {
+          Some(Context::TemplateExpression(depth)) | Some(Context::JsxExpression(depth)) => {
+            *depth += 1
+          }
+          _ => {}
+        }
+      }
+      b'}' => {
+        self.position += 1;
+        self.expression_allowed = true;
+        match self.contexts.last_mut() {
+          Some(Context::TemplateExpression(0)) => {
+            self.contexts.pop();
+          }
+          Some(Context::JsxExpression(0)) => {
+            self.contexts.pop();
+            self.expression_allowed = false;
+          }
+          Some(Context::TemplateExpression(depth)) | Some(Context::JsxExpression(depth)) => {
+            *depth -= 1
+          }
+          _ => {}
+        }
+      }
+      b')' | b']' => {
+        self.position += 1;
+        self.expression_allowed = false;
+      }
+      b'.' if self.peek(1) == Some(b'.') && self.peek(2) == Some(b'.') => {
+        self.position += 3;
+        self.expression_allowed = true;
+      }
+      b'.' if !self.peek(1).map(|b| b.is_ascii_digit()).unwrap_or(false) => {
+        self.position += 1;
+        self.expression_allowed = false;
+        self.after_dot = true;
+      }
+      b'<' if self.jsx && self.expression_allowed && self.looks_like_jsx() => {
+        self.position += 1;
+        self.contexts.push(Context::JsxOpeningTag);
+      }
+      byte if byte.is_ascii_digit() || byte == b'.' => {
+        self.position += 1;
+        while self
+          .peek(0)
+          .map(|b| is_identifier_part(b) || b == b'.')
+          .unwrap_or(false)
+        {
+          self.position += 1;
+        }
+        self.expression_allowed = false;
+      }
+      byte if is_identifier_start(byte) => {
+        let start = self.position;
+        let word = self.read_word();
+        self.expression_allowed = !after_dot && KEYWORDS_BEFORE_EXPRESSION.contains(&word);
+        if !after_dot && IMPORT_KEYWORDS.contains(&word) {
+          return Some(start);
+        }
+      }
+      _ => {
+        self.position += 1;
+        self.expression_allowed = true;
+      }
+    }
+
+    None
+  }
+
+  fn step_template(&mut self) {
+    match self.peek(0) {
+      Some(b'\\') => self.position += 2,
+      Some(b'`') => {
+        self.position += 1;
+        self.contexts.pop();
+        self.expression_allowed = false;
+      }
+      Some(b'$') if self.peek(1) == Some(b'{') => {
+        self.position += 2;
+        self.contexts.push(Context::TemplateExpression(0));
+        self.expression_allowed = true;
+      }
+      _ => self.position += 1,
+    }
+  }
+
+  fn step_jsx_tag(&mut self, closing: bool) {
+    match self.peek(0) {
+      Some(byte @ b'\'') | Some(byte @ b'"') => self.skip_string(byte),
+      Some(b'{') => {
+        self.position += 1;
+        self.contexts.push(Context::JsxExpression(0));
+        self.expression_allowed = true;
+      }
+      Some(b'/') if self.peek(1) == Some(b'>') => {
+        // self closing, there are no children
+        self.position += 2;
+        self.contexts.pop();
+        self.expression_allowed = false;
+      }
+      Some(b'>') => {
+        self.position += 1;
+        self.contexts.pop();
+        if closing {
+          // this also closes the children of the element
+          if self.contexts.last() == Some(&Context::JsxChildren) {
+            self.contexts.pop();
+          }
+          self.expression_allowed = false;
+        } else {
+          self.contexts.push(Context::JsxChildren);
+        }
+      }
+      _ => self.position += 1,
+    }
+  }
+
+  fn step_jsx_children(&mut self) {
+    match self.peek(0) {
+      Some(b'{') => {
+        self.position += 1;
+        self.contexts.push(Context::JsxExpression(0));
+        self.expression_allowed = true;
+      }
+      Some(b'<') if self.peek(1) == Some(b'/') => {
+        self.position += 2;
+        self.contexts.push(Context::JsxClosingTag);
+      }
+      Some(b'<') => {
+        self.position += 1;
+        self.contexts.push(Context::JsxOpeningTag);
+      }
+      _ => self.position += 1,
+    }
+  }
+}
+
+impl<'a> Iterator for CodeScanner<'a> {
+  type Item = usize;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while self.position < self.source.len() {
+      let found = match self.contexts.last() {
+        None | Some(Context::TemplateExpression(_)) | Some(Context::JsxExpression(_)) => {
+          self.step_code()
+        }
+        Some(Context::Template) => {
+          self.step_template();
+          None
+        }
+        Some(Context::JsxOpeningTag) => {
+          self.step_jsx_tag(false);
+          None
+        }
+        Some(Context::JsxClosingTag) => {
+          self.step_jsx_tag(true);
+          None
+        }
+        Some(Context::JsxChildren) => {
+          self.step_jsx_children();
+          None
+        }
+      };
+
+      if found.is_some() {
+        return found;
+      }
+    }
+
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn keywords(source: &str, jsx: bool) -> Vec<&str> {
+    CodeScanner::new(source, jsx)
+      .map(|offset| {
+        let rest = &source[offset..];
+        &rest[..rest
+          .find(|c: char| !c.is_alphanumeric())
+          .unwrap_or(rest.len())]
+      })
+      .collect()
+  }
+
+  #[test]
+  fn it_skips_comments_and_strings() {
+    let source = r#"
+      // require("a")
+      /* import b from "b" */
+      const c = "require('c')";
+      const d = 'import("d")';
+      require("e");
+    "#;
+
+    assert_eq!(keywords(source, true), vec!["require"]);
+  }
+
+  #[test]
+  fn it_looks_inside_template_expressions_only() {
+    let source = r#"
+      const a = `require("a") ${require("b")} ${ { x: `${import("c")}` }.x } import`;
+      export default a;
+    "#;
+
+    assert_eq!(keywords(source, true), vec!["require", "import", "export"]);
+  }
+
+  #[test]
+  fn it_skips_regex_literals_but_not_divisions() {
+    let source = r#"
+      const a = /require\("a"\)[/]/g.test(x);
+      const b = a / 2; require("b") / 3;
+      return /import/;
+    "#;
+
+    assert_eq!(keywords(source, true), vec!["require"]);
+  }
+
+  #[test]
+  fn it_skips_jsx_text() {
+    let source = r#"
+      const a = (
+        <div className="require('a')" onClick={() => import("./b")}>
+          require("c") and <b>import("d")</b>
+          <>{require("e")}</>
+          <br />
+        </div>
+      );
+      export default a;
+    "#;
+
+    assert_eq!(keywords(source, true), vec!["import", "require", "export"]);
+  }
+
+  #[test]
+  fn it_does_not_treat_comparisons_and_generics_as_jsx() {
+    let source = r#"
+      const a = b < c ? require("d") : null;
+      const e = <T,>(x: T) => require("f");
+      function g<T>(x: Array<T>) { return import("h") }
+    "#;
+
+    assert_eq!(keywords(source, true), vec!["require", "require", "import"]);
+    assert_eq!(
+      keywords(source, false),
+      vec!["require", "require", "import"]
+    );
+  }
+
+  #[test]
+  fn it_only_matches_whole_words_outside_of_member_access() {
+    let source = r#"
+      reimport("a");
+      requireable("b");
+      module.require("c");
+      [...require("d")];
+    "#;
+
+    assert_eq!(keywords(source, true), vec!["require"]);
+  }
+}
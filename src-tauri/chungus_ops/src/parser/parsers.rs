@@ -1,67 +1,303 @@
-use crate::parser::Import;
+use crate::parser::{Import, ImportedBindings, NamedBinding};
 use nom::branch::alt;
-use nom::bytes::complete::{is_not, tag, take_until};
+use nom::bytes::complete::{is_not, tag};
 use nom::character::complete::char;
 use nom::character::complete::multispace0;
+use nom::combinator::recognize;
 use nom::error::ErrorKind;
 use nom::sequence::{delimited, terminated, tuple};
 use nom::IResult;
 use std::path::PathBuf;
+use ClauseToken::{Name, Punctuation, StringLiteral};
 
-pub fn all_possible_import_types(content: &str) -> IResult<&str, Import> {
+/// An import, its specifier string with the quotes included and what it binds
+pub type ParsedImport<'a> = (Import, &'a str, ImportedBindings);
+
+pub fn all_possible_import_types(content: &str) -> IResult<&str, ParsedImport<'_>> {
   alt((
     parse_require_statement,
     parse_import_statement,
+    parse_side_effect_import,
     parse_import_promise,
     parse_export_from,
   ))(content)
 }
 
-fn parse_import_statement(module_contents: &str) -> IResult<&str, Import> {
-  let from = delimited(multispace0, tag("from"), multispace0);
+fn parse_import_statement(module_contents: &str) -> IResult<&str, ParsedImport<'_>> {
   let import = terminated(tag("import"), multispace0);
-  let (next, output) = tuple((import, take_until("from"), from, path_string))(module_contents)?;
+  let (next, (_import_text, clause, (path, specifier))) =
+    tuple((import, from_clause, path_string))(module_contents)?;
 
-  let (_import_text, meat, _from, path) = output;
-  let only_contains_type_regex = regex::Regex::new(r#"\{\s*type\s+\w+,?\s+\}"#).unwrap();
-  if meat.starts_with("type") || only_contains_type_regex.is_match(&meat) {
-    return Err(nom::Err::Error(nom::error::Error::new(
+  let bindings = if is_type_only_clause(&clause) {
+    None
+  } else {
+    import_bindings(&clause)
+  };
+  match bindings {
+    Some(bindings) => Ok((next, (Import::Import(path), specifier, bindings))),
+    None => Err(nom::Err::Error(nom::error::Error::new(
       next,
       ErrorKind::Fail,
-    )));
+    ))),
   }
+}
+
+/// `import "./polyfill"`
+fn parse_side_effect_import(module_contents: &str) -> IResult<&str, ParsedImport<'_>> {
+  let import = terminated(tag("import"), multispace0);
+  let (next, (_, (path, specifier))) = tuple((import, path_string))(module_contents)?;
+
+  Ok((
+    next,
+    (
+      Import::Import(path),
+      specifier,
+      ImportedBindings::SideEffect,
+    ),
+  ))
+}
 
-  Ok((next, Import::Import(path)))
+/// A word, string or punctuation in the clause between `import` / `export` and `from`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClauseToken<'a> {
+  Name(&'a str),
+  /// The contents of a string, these can only be names in `{ }` or after `* as`
+  StringLiteral(&'a str),
+  Punctuation(char),
 }
 
-fn parse_import_promise(module_contents: &str) -> IResult<&str, Import> {
-  let (next, output) = delimited(tag("import("), path_string, tag(")"))(module_contents)?;
+/// Everything between `import` / `export` and the module specifier, up to and including `from`.
+/// Comments are skipped. Anything that can't be in the clause, like parentheses, `=` or `;`, fails
+/// the match straight away so a failed match doesn't scan the rest of the file.
+fn from_clause(input: &str) -> IResult<&str, Vec<ClauseToken<'_>>> {
+  let fail = |at| nom::Err::Error(nom::error::Error::new(at, ErrorKind::TakeUntil));
+  let mut tokens = vec![];
+  let mut in_braces = false;
+  let mut rest = input;
+  loop {
+    rest = rest.trim_start();
+    if let Some(comment) = rest.strip_prefix("//") {
+      rest = comment.find('\n').map_or("", |end| &comment[end..]);
+      continue;
+    }
+    if let Some(comment) = rest.strip_prefix("/*") {
+      let end = comment.find("*/").ok_or_else(|| fail(rest))?;
+      rest = &comment[end + 2..];
+      continue;
+    }
 
-  Ok((next, Import::AsyncImport(output)))
+    match rest.chars().next() {
+      // names in `{ }` and after `* as` can be strings
+      Some('\'') | Some('"') if in_braces || tokens.last() == Some(&Name("as")) => {
+        let (next, value) = string_literal(rest)?;
+        tokens.push(StringLiteral(value));
+        rest = next;
+      }
+      // the module specifier
+      Some('\'') | Some('"') => {
+        return match tokens.pop() {
+          Some(Name("from")) => Ok((rest, tokens)),
+          _ => Err(fail(rest)),
+        }
+      }
+      Some(c) if matches!(c, '{' | '}' | ',' | '*') => {
+        in_braces = match c {
+          '{' => true,
+          '}' => false,
+          _ => in_braces,
+        };
+        tokens.push(Punctuation(c));
+        rest = &rest[1..];
+      }
+      Some(c) if is_name_character(c) => {
+        let end = rest.find(|c| !is_name_character(c)).unwrap_or(rest.len());
+        tokens.push(Name(&rest[..end]));
+        rest = &rest[end..];
+      }
+      _ => return Err(fail(rest)),
+    }
+  }
+}
+
+fn is_name_character(c: char) -> bool {
+  c.is_alphanumeric() || c == '_' || c == '$'
 }
 
-fn parse_require_statement(module_contents: &str) -> IResult<&str, Import> {
-  let (next, output) = delimited(tag("require("), path_string, tag(")"))(module_contents)?;
+/// The contents of a string literal, strings can't span lines
+fn string_literal(input: &str) -> IResult<&str, &str> {
+  let mut characters = input.char_indices();
+  let quote = characters.next().map(|(_, quote)| quote);
+  let mut escaped = false;
+  for (index, c) in characters {
+    match c {
+      _ if escaped => escaped = false,
+      '\\' => escaped = true,
+      '\n' | '\r' => break,
+      c if Some(c) == quote => return Ok((&input[index + 1..], &input[1..index])),
+      _ => {}
+    }
+  }
+  Err(nom::Err::Error(nom::error::Error::new(
+    input,
+    ErrorKind::Escaped,
+  )))
+}
 
-  Ok((next, Import::Require(output)))
+/// `import type { A } from`, `export type * from` and `import { type A, type B } from` are erased
+/// by typescript
+fn is_type_only_clause(clause: &[ClauseToken]) -> bool {
+  match clause {
+    // a default import called type
+    [Name("type")] | [Name("type"), Punctuation(','), ..] => false,
+    [Name("type"), ..] => true,
+    [Punctuation('{'), list @ .., Punctuation('}')] => {
+      let mut specifiers = list
+        .split(|token| *token == Punctuation(','))
+        .filter(|specifier| !specifier.is_empty())
+        .peekable();
+      specifiers.peek().is_some() && specifiers.all(is_type_only_specifier)
+    }
+    _ => false,
+  }
+}
+
+fn is_type_only_specifier(specifier: &[ClauseToken]) -> bool {
+  matches!(
+    specifier,
+    [Name("type"), _] | [Name("type"), _, Name("as"), _]
+  )
+}
+
+/// What the clause between `import` and `from` binds, nothing when it isn't one
+fn import_bindings(clause: &[ClauseToken]) -> Option<ImportedBindings> {
+  // `import defer * as a from` and `import source a from` load the module in a different phase
+  let clause = match clause {
+    [Name("defer"), rest @ ..] | [Name("source"), rest @ ..]
+      if !rest.is_empty() && rest[0] != Punctuation(',') =>
+    {
+      rest
+    }
+    _ => clause,
+  };
+
+  let (default, rest) = match clause {
+    [Name(default)] => (Some(default.to_string()), &[][..]),
+    [Name(default), Punctuation(','), rest @ ..] if !rest.is_empty() => {
+      (Some(default.to_string()), rest)
+    }
+    _ => (None, clause),
+  };
+  let (namespace, named) = match rest {
+    [] => (None, vec![]),
+    [Punctuation('*'), Name("as"), Name(namespace)] => (Some(namespace.to_string()), vec![]),
+    [Punctuation('{'), list @ .., Punctuation('}')] => (None, named_bindings(list)?),
+    _ => return None,
+  };
+
+  Some(ImportedBindings::Import {
+    default,
+    namespace,
+    named,
+  })
+}
+
+/// What the clause between `export` and `from` exports again, nothing when it isn't one
+fn export_bindings(clause: &[ClauseToken]) -> Option<ImportedBindings> {
+  match clause {
+    [Punctuation('*')] => Some(ImportedBindings::ExportAll),
+    [Punctuation('*'), Name("as"), name] => {
+      export_name(name).map(ImportedBindings::ExportNamespace)
+    }
+    [Punctuation('{'), list @ .., Punctuation('}')] => {
+      named_bindings(list).map(ImportedBindings::ExportNamed)
+    }
+    _ => None,
+  }
 }
 
-fn parse_export_from(module_contents: &str) -> IResult<&str, Import> {
-  let from = delimited(multispace0, tag("from"), multispace0);
+/// The names in `{ a, b as c, "d" as e }`. Typescript's `type A` specifiers are left out, they
+/// are erased.
+fn named_bindings(list: &[ClauseToken]) -> Option<Vec<NamedBinding>> {
+  let mut named = vec![];
+  for specifier in list.split(|token| *token == Punctuation(',')) {
+    let binding = match specifier {
+      [] => continue,
+      _ if is_type_only_specifier(specifier) => continue,
+      [name] => NamedBinding {
+        name: export_name(name)?,
+        alias: None,
+      },
+      [name, Name("as"), alias] => NamedBinding {
+        name: export_name(name)?,
+        alias: Some(export_name(alias)?),
+      },
+      _ => return None,
+    };
+    named.push(binding);
+  }
+  Some(named)
+}
+
+/// Names in an import or export list can be strings, `export { "a-b" as c }`
+fn export_name(token: &ClauseToken) -> Option<String> {
+  match token {
+    Name(name) | StringLiteral(name) => Some(name.to_string()),
+    Punctuation(_) => None,
+  }
+}
+
+fn parse_import_promise(module_contents: &str) -> IResult<&str, ParsedImport<'_>> {
+  let (next, (path, specifier)) =
+    delimited(tag("import("), path_string, tag(")"))(module_contents)?;
+
+  Ok((
+    next,
+    (
+      Import::AsyncImport(path),
+      specifier,
+      ImportedBindings::Unknown,
+    ),
+  ))
+}
+
+fn parse_require_statement(module_contents: &str) -> IResult<&str, ParsedImport<'_>> {
+  let (next, (path, specifier)) =
+    delimited(tag("require("), path_string, tag(")"))(module_contents)?;
+
+  Ok((
+    next,
+    (Import::Require(path), specifier, ImportedBindings::Unknown),
+  ))
+}
+
+fn parse_export_from(module_contents: &str) -> IResult<&str, ParsedImport<'_>> {
   let export = terminated(tag("export"), multispace0);
-  let (next, output) = tuple((export, take_until("from"), from, path_string))(module_contents)?;
+  let (next, (_, clause, (path, specifier))) =
+    tuple((export, from_clause, path_string))(module_contents)?;
 
-  let (_, _, _, path) = output;
-  Ok((next, Import::ExportFrom(path)))
+  let bindings = if is_type_only_clause(&clause) {
+    None
+  } else {
+    export_bindings(&clause)
+  };
+  match bindings {
+    Some(bindings) => Ok((next, (Import::ExportFrom(path), specifier, bindings))),
+    None => Err(nom::Err::Error(nom::error::Error::new(
+      next,
+      ErrorKind::Fail,
+    ))),
+  }
 }
 
-fn path_string(input: &str) -> IResult<&str, PathBuf> {
-  let (next, output) = alt((
+/// The path in a specifier string, and the whole string
+fn path_string(input: &str) -> IResult<&str, (PathBuf, &str)> {
+  let (next, specifier) = recognize(alt((
     delimited(char('\''), is_not("'"), char('\'')),
     delimited(char('"'), is_not("\""), char('"')),
-  ))(input)?;
+  )))(input)?;
 
-  Ok((next, PathBuf::from(output)))
+  let path = &specifier[1..specifier.len() - 1];
+  Ok((next, (PathBuf::from(path), specifier)))
 }
 
 #[cfg(test)]
@@ -71,6 +307,16 @@ mod tests {
   use crate::parser::Import::AsyncImport;
   use std::path::PathBuf;
 
+  fn named(bindings: &[(&str, Option<&str>)]) -> Vec<NamedBinding> {
+    bindings
+      .iter()
+      .map(|(name, alias)| NamedBinding {
+        name: name.to_string(),
+        alias: alias.map(str::to_string),
+      })
+      .collect()
+  }
+
   #[test]
   fn parses_import_statements() {
     let source = r#"import potato from "ramda" "#;
@@ -79,7 +325,18 @@ mod tests {
     assert!(output.is_ok(), "{:?}", &output);
     assert_eq!(
       output.unwrap(),
-      (" ", Import::Import(PathBuf::from("ramda")))
+      (
+        " ",
+        (
+          Import::Import(PathBuf::from("ramda")),
+          "\"ramda\"",
+          ImportedBindings::Import {
+            default: Some("potato".to_string()),
+            namespace: None,
+            named: vec![],
+          }
+        )
+      )
     );
 
     let source = r#"import { something, x } from "ramda"; "#;
@@ -89,7 +346,18 @@ mod tests {
     assert!(output.is_ok(), "{:?}", &output);
     assert_eq!(
       output.unwrap(),
-      ("; ", Import::Import(PathBuf::from("ramda")))
+      (
+        "; ",
+        (
+          Import::Import(PathBuf::from("ramda")),
+          "\"ramda\"",
+          ImportedBindings::Import {
+            default: None,
+            namespace: None,
+            named: named(&[("something", None), ("x", None)]),
+          }
+        )
+      )
     );
 
     let source = r#"import { 
@@ -102,17 +370,74 @@ mod tests {
     assert!(output.is_ok(), "{:?}", &output);
     assert_eq!(
       output.unwrap(),
-      ("; ", Import::Import(PathBuf::from("ramda")))
+      (
+        "; ",
+        (
+          Import::Import(PathBuf::from("ramda")),
+          "\"ramda\"",
+          ImportedBindings::Import {
+            default: None,
+            namespace: None,
+            named: named(&[("something", None), ("x", None)]),
+          }
+        )
+      )
     );
   }
 
+  #[test]
+  fn it_skips_type_only_imports() {
+    for source in [
+      r#"import type { A } from "a""#,
+      r#"import type A from "a""#,
+      r#"import { type A } from "a""#,
+      r#"import { type A, type B, } from "a""#,
+    ]
+    .iter()
+    {
+      assert!(parse_import_statement(source).is_err(), "{}", source);
+    }
+
+    for source in [
+      r#"import type from "a""#,
+      r#"import { type A, B } from "a""#,
+      r#"import typeface from "a""#,
+    ]
+    .iter()
+    {
+      assert!(parse_import_statement(source).is_ok(), "{}", source);
+    }
+  }
+
+  #[test]
+  fn it_stops_looking_for_from_at_the_end_of_the_clause() {
+    let source = r#"import(foo); const x = 1; export { a } from "./a""#;
+    assert!(parse_import_statement(source).is_err());
+
+    let source = r#"export default function from() {}; export * from "./b""#;
+    assert!(parse_export_from(source).is_err());
+
+    let source = r#"import wherefrom "./c""#;
+    assert!(parse_import_statement(source).is_err());
+  }
+
   #[test]
   fn it_parses_async_imports() {
     let source = r#"import("ramda")"#;
     let output = parse_import_promise(&source);
 
     assert!(output.is_ok(), "{:?}", &output);
-    assert_eq!(output.unwrap(), ("", AsyncImport(PathBuf::from("ramda"))))
+    assert_eq!(
+      output.unwrap(),
+      (
+        "",
+        (
+          AsyncImport(PathBuf::from("ramda")),
+          "\"ramda\"",
+          ImportedBindings::Unknown
+        )
+      )
+    )
   }
 
   #[test]
@@ -123,7 +448,14 @@ mod tests {
     assert!(output.is_ok(), "{:?}", &output);
     assert_eq!(
       output.unwrap(),
-      ("", Import::ExportFrom(PathBuf::from("./local")))
+      (
+        "",
+        (
+          Import::ExportFrom(PathBuf::from("./local")),
+          "\"./local\"",
+          ImportedBindings::ExportAll
+        )
+      )
     )
   }
 
@@ -135,7 +467,173 @@ mod tests {
     assert!(output.is_ok(), "{:?}", &output);
     assert_eq!(
       output.unwrap(),
-      ("", Import::Require(PathBuf::from("ramda")))
+      (
+        "",
+        (
+          Import::Require(PathBuf::from("ramda")),
+          "\"ramda\"",
+          ImportedBindings::Unknown
+        )
+      )
     )
   }
+
+  #[test]
+  fn it_records_what_imports_bind() {
+    let bindings = |source: &str| all_possible_import_types(source).unwrap().1 .2;
+    let import = |default: Option<&str>, namespace: Option<&str>, named| ImportedBindings::Import {
+      default: default.map(str::to_string),
+      namespace: namespace.map(str::to_string),
+      named,
+    };
+
+    assert_eq!(
+      bindings(r#"import "./polyfill""#),
+      ImportedBindings::SideEffect
+    );
+    assert_eq!(
+      bindings(r#"import * as R from "ramda""#),
+      import(None, Some("R"), vec![])
+    );
+    assert_eq!(
+      bindings(r#"import React, * as all from "react""#),
+      import(Some("React"), Some("all"), vec![])
+    );
+    assert_eq!(
+      bindings(r#"import React, { useState as state, default as D, type FC } from "react""#),
+      import(
+        Some("React"),
+        None,
+        named(&[("useState", Some("state")), ("default", Some("D"))])
+      )
+    );
+    assert_eq!(
+      bindings(r#"import {} from "a""#),
+      import(None, None, vec![])
+    );
+    assert_eq!(bindings(r#"require("a")"#), ImportedBindings::Unknown);
+
+    assert_eq!(
+      bindings(r#"export * from "a""#),
+      ImportedBindings::ExportAll
+    );
+    assert_eq!(
+      bindings(r#"export * as a from "a""#),
+      ImportedBindings::ExportNamespace("a".to_string())
+    );
+    assert_eq!(
+      bindings(r#"export { a, b as c, } from "a""#),
+      ImportedBindings::ExportNamed(named(&[("a", None), ("b", Some("c"))]))
+    );
+  }
+
+  #[test]
+  fn it_recognises_every_import_and_export_from_form() {
+    let import = |default: Option<&str>, namespace: Option<&str>, named| ImportedBindings::Import {
+      default: default.map(str::to_string),
+      namespace: namespace.map(str::to_string),
+      named,
+    };
+    let forms = vec![
+      (r#"import "./styles.css""#, ImportedBindings::SideEffect),
+      (r#"import './setup'"#, ImportedBindings::SideEffect),
+      (r#"import"./min""#, ImportedBindings::SideEffect),
+      (r#"import x from "./x""#, import(Some("x"), None, vec![])),
+      (
+        r#"import * as x from "./x""#,
+        import(None, Some("x"), vec![]),
+      ),
+      (
+        r#"import x, * as y from "./x""#,
+        import(Some("x"), Some("y"), vec![]),
+      ),
+      (
+        r#"import x, { y } from "./x""#,
+        import(Some("x"), None, named(&[("y", None)])),
+      ),
+      (
+        r#"import{x as y}from"./x""#,
+        import(None, None, named(&[("x", Some("y"))])),
+      ),
+      (
+        r#"import { "a-b" as ab, from } from "./x""#,
+        import(None, None, named(&[("a-b", Some("ab")), ("from", None)])),
+      ),
+      (
+        r#"import from from "./x""#,
+        import(Some("from"), None, vec![]),
+      ),
+      (
+        r#"import /* a */ x, // b
+          { y } from "./x""#,
+        import(Some("x"), None, named(&[("y", None)])),
+      ),
+      (
+        r#"import source x from "./x.wasm""#,
+        import(Some("x"), None, vec![]),
+      ),
+      (
+        r#"import defer * as x from "./x""#,
+        import(None, Some("x"), vec![]),
+      ),
+      (r#"export * from "./x""#, ImportedBindings::ExportAll),
+      (
+        r#"export * as x from "./x""#,
+        ImportedBindings::ExportNamespace("x".to_string()),
+      ),
+      (
+        r#"export * as "a b" from "./x""#,
+        ImportedBindings::ExportNamespace("a b".to_string()),
+      ),
+      (
+        r#"export {} from "./x""#,
+        ImportedBindings::ExportNamed(vec![]),
+      ),
+      (
+        "export {\n  default,\n  \"from\" as x,\n} from './x'",
+        ImportedBindings::ExportNamed(named(&[("default", None), ("from", Some("x"))])),
+      ),
+      (
+        r#"export{x as default}from"./x""#,
+        ImportedBindings::ExportNamed(named(&[("x", Some("default"))])),
+      ),
+    ];
+
+    for (source, bindings) in forms {
+      let output = all_possible_import_types(source);
+      assert!(output.is_ok(), "{}: {:?}", source, &output);
+      let (remaining, (_, specifier, output)) = output.unwrap();
+      assert_eq!(remaining, "", "{}", source);
+      assert!(source.ends_with(specifier), "{}", source);
+      assert_eq!(output, bindings, "{}", source);
+    }
+
+    // import attributes come after the specifier
+    let (remaining, (_, specifier, _)) =
+      all_possible_import_types(r#"import x from "./x" with { type: "json" }"#).unwrap();
+    assert_eq!(
+      (remaining, specifier),
+      (r#" with { type: "json" }"#, "\"./x\"")
+    );
+  }
+
+  #[test]
+  fn it_rejects_clauses_that_are_not_imports() {
+    for source in [
+      r#"export type { A } from "./a""#,
+      r#"export type * from "./a""#,
+      r#"export { type A } from "./a""#,
+      r#"export default class A extends B {} import x from "./x""#,
+      r#"export default from "./a""#,
+      r#"import { a, { b } } from "./a""#,
+      r#"import a, from "./a""#,
+      r#"import.meta.url; import x from "./x""#,
+      "import { 'a\n' as b } from \"./a\"",
+    ]
+    .iter()
+    {
+      let output = all_possible_import_types(source);
+      assert!(output.is_err(), "{}: {:?}", source, &output);
+    }
+  }
 }
@@ -0,0 +1,493 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::diagnostics::Diagnostics;
+use crate::error::CoreError;
+use crate::file::{process_javascript_file, process_package_json};
+use crate::logging::ClientSideLogger;
+use crate::module::{Asset, Location, Module};
+use crate::resolve::{Alias, Resolver};
+use crate::DependencyCache;
+
+// Bumped whenever what is stored changes shape or the same file would be parsed into different
+// imports, older cache files are then thrown away
+const CACHE_VERSION: u32 = 4;
+
+// Everything on the resolver that changes where an import ends up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ResolverSettings {
+  recursively_resolve_node_modules: bool,
+  resolve_root: Location,
+  extensions: Vec<String>,
+  included_directories: Vec<PathBuf>,
+  module_directories: Vec<String>,
+  aliases: Vec<Alias>,
+  tsconfig: Option<PathBuf>,
+  conditions: Vec<String>,
+}
+
+impl ResolverSettings {
+  fn new(resolver: &Resolver) -> Self {
+    let mut extensions: Vec<String> = resolver.extensions.iter().cloned().collect();
+    extensions.sort();
+    Self {
+      recursively_resolve_node_modules: resolver.recursively_resolve_node_modules,
+      resolve_root: resolver.resolve_root.clone(),
+      extensions,
+      included_directories: resolver.included_directories.clone(),
+      module_directories: resolver.module_directories.clone(),
+      aliases: resolver.aliases.clone(),
+      tsconfig: resolver.tsconfig.clone(),
+      conditions: resolver.conditions.clone(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredModule {
+  // the file that was read, the package.json for node modules
+  file: Location,
+  modified: SystemTime,
+  hash: String,
+  module: Module,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredCache {
+  version: u32,
+  settings: ResolverSettings,
+  // hashes of the tsconfig.json and package.json files the resolver read
+  configuration: BTreeMap<PathBuf, String>,
+  modules: Vec<(Location, StoredModule)>,
+}
+
+/// Parsed and resolved modules kept on disk between runs. A module is used again as long as the
+/// file it came from has the same modification time, or the same content hash when only the
+/// time changed, and everything it imports still exists.
+///
+/// Everything is parsed again when the resolver settings, or a tsconfig.json or package.json
+/// that was read to resolve imports, changed. A new file that an unchanged import would now
+/// resolve to is not picked up until the cache file is removed.
+pub struct PersistentCache {
+  path: PathBuf,
+  settings: ResolverSettings,
+  configuration: BTreeMap<PathBuf, String>,
+  modules: HashMap<Location, StoredModule>,
+}
+
+impl PersistentCache {
+  /// node_modules/.cache/chungus under the resolve root, where other build tools keep theirs
+  pub fn default_path(resolver: &Resolver) -> PathBuf {
+    resolver
+      .resolve_root
+      .as_ref()
+      .join("node_modules")
+      .join(".cache")
+      .join("chungus")
+      .join("dependency-cache.json")
+  }
+
+  /// Reads the cache file at `path`. A missing or unreadable file, or one written with other
+  /// resolver settings, gives an empty cache so everything is parsed again.
+  #[tracing::instrument(skip(path, resolver))]
+  pub fn load(path: impl AsRef<Path>, resolver: &Resolver) -> Self {
+    let path = path.as_ref();
+    let settings = ResolverSettings::new(resolver);
+    let mut cache = Self {
+      path: path.to_path_buf(),
+      settings,
+      configuration: BTreeMap::new(),
+      modules: HashMap::new(),
+    };
+
+    let stored = match File::open(path) {
+      Ok(file) => serde_json::from_reader::<_, StoredCache>(BufReader::new(file)),
+      Err(e) => {
+        tracing::info!("No dependency cache at {:?}: {}", path, e);
+        return cache;
+      }
+    };
+
+    match stored {
+      Ok(stored) if stored.version != CACHE_VERSION => {
+        tracing::info!("Dependency cache {:?} is from another version", path)
+      }
+      Ok(stored) if stored.settings != cache.settings => {
+        tracing::info!("Resolver settings changed, rebuilding {:?}", path)
+      }
+      Ok(stored) => match stored
+        .configuration
+        .iter()
+        .find(|(file, hash)| hash_file(file).ok().as_ref() != Some(*hash))
+      {
+        Some((file, _)) => tracing::info!("{:?} changed, rebuilding {:?}", file, path),
+        None => {
+          tracing::info!("Loaded {} cached modules", stored.modules.len());
+          cache.configuration = stored.configuration;
+          cache.modules = stored.modules.into_iter().collect();
+        }
+      },
+      Err(e) => tracing::warn!("Could not read the dependency cache {:?}: {}", path, e),
+    }
+    cache
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  pub fn len(&self) -> usize {
+    self.modules.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.modules.is_empty()
+  }
+
+  /// Writes the cache back to its file. Modules whose file was deleted are left out.
+  #[tracing::instrument(skip(self))]
+  pub fn save(&self) -> Result<(), CoreError> {
+    if let Some(parent) = self.path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+
+    let mut modules: Vec<(Location, StoredModule)> = self
+      .modules
+      .iter()
+      .filter(|(_, stored)| stored.file.as_ref().exists())
+      .map(|(location, stored)| (location.clone(), stored.clone()))
+      .collect();
+    modules.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let stored = StoredCache {
+      version: CACHE_VERSION,
+      settings: self.settings.clone(),
+      configuration: self.configuration.clone(),
+      modules,
+    };
+    let file = OpenOptions::new()
+      .write(true)
+      .create(true)
+      .truncate(true)
+      .open(&self.path)?;
+    serde_json::to_writer(BufWriter::new(file), &stored)?;
+
+    tracing::info!("Saved {} cached modules", stored.modules.len());
+    Ok(())
+  }
+
+  // The stored module for `location` if it is still up to date
+  fn fresh_module(&mut self, location: &Location) -> Option<Module> {
+    let stored = self.modules.get_mut(location)?;
+    let modified = std::fs::metadata(&stored.file)
+      .and_then(|metadata| metadata.modified())
+      .ok()?;
+
+    if modified != stored.modified {
+      let hash = hash_file(&stored.file).ok()?;
+      if hash != stored.hash {
+        return None;
+      }
+      // touched but not changed
+      stored.modified = modified;
+    }
+
+    // something it imports was deleted, the import resolves somewhere else now
+    let missing_dependency = stored
+      .module
+      .dependencies
+      .iter()
+      .filter_map(|dependency| dependency.location())
+      .any(|dependency| !dependency.as_ref().exists());
+    if missing_dependency {
+      return None;
+    }
+
+    Some(stored.module.clone())
+  }
+
+  // The module for `location`, read from `file` with `process` when the stored one is out of date
+  fn module(
+    &mut self,
+    location: &Location,
+    file: &Location,
+    process: impl FnOnce() -> Result<Module, CoreError>,
+  ) -> Result<Module, CoreError> {
+    if let Some(module) = self.fresh_module(location) {
+      tracing::trace!("Using cached {:?}", location);
+      return Ok(module);
+    }
+
+    // the time is taken first so a write while parsing makes it out of date next time
+    let modified = std::fs::metadata(file)?.modified()?;
+    let hash = hash_file(file)?;
+    let module = process()?;
+    self.modules.insert(
+      location.clone(),
+      StoredModule {
+        file: file.clone(),
+        modified,
+        hash,
+        module: module.clone(),
+      },
+    );
+    Ok(module)
+  }
+
+  // Remembers the configuration files the resolver read. The ones that were stored before are
+  // kept, nothing needs to be resolved again for a run that only uses cached modules.
+  fn record_configuration(&mut self, resolver: &Resolver) {
+    for file in resolver.configuration_files() {
+      match hash_file(&file) {
+        Ok(hash) => {
+          self.configuration.insert(file, hash);
+        }
+        Err(e) => tracing::warn!("Could not hash {:?}: {}", file, e),
+      }
+    }
+  }
+}
+
+fn hash_file(file: impl AsRef<Path>) -> Result<String, CoreError> {
+  let mut contents = vec![];
+  File::open(file)?.read_to_end(&mut contents)?;
+  Ok(format!("{:x}", Sha256::digest(&contents)))
+}
+
+/// `build_dependency_cache` that only reads and resolves the files that changed since
+/// `persistent` was saved. Any file other than the entrypoint that can't be read is left out of
+/// the cache and reported in the diagnostics. `persistent` is updated but not saved.
+#[tracing::instrument(skip(resolver, target, cache, persistent, logger))]
+pub fn build_dependency_cache_persistent(
+  resolver: &Resolver,
+  target: &Location,
+  cache: &mut DependencyCache,
+  persistent: &mut PersistentCache,
+  logger: &impl ClientSideLogger,
+) -> Result<Diagnostics, CoreError> {
+  tracing::info!(
+    "Start build dependency cache {:?} with {} cached modules",
+    target,
+    persistent.len()
+  );
+
+  let root_module =
+    persistent.module(target, target, || process_javascript_file(resolver, target))?;
+  logger.message("Loaded root module");
+  let mut diagnostics = Diagnostics::default();
+  diagnostics.add_unresolved_imports(&root_module);
+  cache.insert(target.clone(), root_module.clone());
+
+  let mut queue = vec![root_module];
+  while let Some(module) = queue.pop() {
+    for dependency in module.dependencies.iter() {
+      let location = match dependency.location() {
+        Some(location) => location,
+        None => {
+          tracing::debug!("{:?} could not be resolved", dependency);
+          continue;
+        }
+      };
+      if cache.contains_key(&location) {
+        continue;
+      }
+
+      let loaded = match dependency.asset() {
+        Asset::NodePackage {
+          target_file,
+          package_directory,
+        } => {
+          logger.message(format!("Resolving node module {:?}", &package_directory));
+          persistent
+            .module(target_file, package_directory, || {
+              process_package_json(resolver, package_directory, dependency.kind())
+            })
+            .inspect(|module| {
+              if target_file != package_directory {
+                cache.insert(package_directory.clone(), module.clone());
+              }
+              cache.insert(target_file.clone(), module.clone());
+            })
+        }
+        Asset::Module(path) => {
+          logger.message(format!("Resolving module {:?}", &path));
+          persistent
+            .module(path, path, || process_javascript_file(resolver, path))
+            .inspect(|module| {
+              cache.insert(path.clone(), module.clone());
+            })
+        }
+        Asset::Asset(_) | Asset::Unresolved(_) | Asset::Ignored(_) => continue,
+      };
+
+      match loaded {
+        Ok(next) => {
+          diagnostics.add_unresolved_imports(&next);
+          queue.push(next);
+        }
+        Err(e) => {
+          let (file, is_package_json) = match dependency.asset() {
+            Asset::NodePackage {
+              package_directory, ..
+            } => (package_directory, true),
+            _ => (&location, false),
+          };
+          diagnostics.add_file_error(file, Some(&module.location), is_package_json, &e);
+        }
+      }
+    }
+  }
+  diagnostics.sort();
+  persistent.record_configuration(resolver);
+
+  tracing::info!("Built dependency cache {:?}", target);
+  Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::build_dependency_cache;
+  use crate::logging::NoopLogger;
+
+  #[test]
+  fn it_reuses_unchanged_files_between_runs() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_cycles");
+    let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    let entrypoint = Location::new(root.join("index.js")).unwrap();
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("cache").join("dependency-cache.json");
+
+    let mut expected = DependencyCache::new();
+    build_dependency_cache(&resolver, &entrypoint, &mut expected, &NoopLogger).unwrap();
+
+    let mut persistent = PersistentCache::load(&path, &resolver);
+    assert!(persistent.is_empty());
+    let mut cache = DependencyCache::new();
+    build_dependency_cache_persistent(
+      &resolver,
+      &entrypoint,
+      &mut cache,
+      &mut persistent,
+      &NoopLogger,
+    )
+    .unwrap();
+    persistent.save().unwrap();
+    assert_eq!(persistent.len(), expected.len());
+
+    // a stored module is used as is, even if parsing the file again would give something else
+    let mut persistent = PersistentCache::load(&path, &resolver);
+    assert_eq!(persistent.len(), expected.len());
+    persistent
+      .modules
+      .get_mut(&entrypoint)
+      .unwrap()
+      .module
+      .dependencies
+      .clear();
+    let mut cache = DependencyCache::new();
+    build_dependency_cache_persistent(
+      &resolver,
+      &entrypoint,
+      &mut cache,
+      &mut persistent,
+      &NoopLogger,
+    )
+    .unwrap();
+    assert_eq!(cache.len(), 1);
+
+    // unless the file changed
+    persistent.modules.get_mut(&entrypoint).unwrap().hash = String::new();
+    persistent.modules.get_mut(&entrypoint).unwrap().modified = SystemTime::UNIX_EPOCH;
+    let mut cache = DependencyCache::new();
+    build_dependency_cache_persistent(
+      &resolver,
+      &entrypoint,
+      &mut cache,
+      &mut persistent,
+      &NoopLogger,
+    )
+    .unwrap();
+    assert_eq!(cache.len(), expected.len());
+    for (location, module) in expected.iter() {
+      assert_eq!(cache[location].dependencies, module.dependencies);
+    }
+
+    // other settings throw everything away
+    let mut other = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    other.conditions = vec!["node".to_string()];
+    assert!(PersistentCache::load(&path, &other).is_empty());
+  }
+
+  #[test]
+  fn it_rebuilds_when_the_configuration_changes() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path().canonicalize().unwrap();
+    let write = |path: &str, contents: &str| {
+      let path = root.join(path);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, contents).unwrap();
+    };
+    write("tsconfig.json", r#"{ "extends": "./base.json" }"#);
+    write(
+      "base.json",
+      r#"{ "compilerOptions": { "paths": { "~/*": ["src/*"] } } }"#,
+    );
+    write(
+      "src/index.ts",
+      "import a from '~/a'\nimport feature from 'pkg/feature'\n",
+    );
+    write("src/a.ts", "export default 1\n");
+    write(
+      "node_modules/pkg/package.json",
+      r#"{ "name": "pkg", "exports": { "./feature": "./feature.js" } }"#,
+    );
+    write("node_modules/pkg/feature.js", "export default 2\n");
+
+    let path = root.join("dependency-cache.json");
+    let entrypoint = Location::new(root.join("src/index.ts")).unwrap();
+    let build_and_save = || {
+      let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+      let mut persistent = PersistentCache::load(&path, &resolver);
+      build_dependency_cache_persistent(
+        &resolver,
+        &entrypoint,
+        &mut DependencyCache::new(),
+        &mut persistent,
+        &NoopLogger,
+      )
+      .unwrap();
+      persistent.save().unwrap();
+      assert_eq!(persistent.configuration.len(), 3);
+    };
+    let load = || {
+      let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+      PersistentCache::load(&path, &resolver)
+    };
+
+    build_and_save();
+    assert!(!load().is_empty());
+    // a config that is only extended
+    write(
+      "base.json",
+      r#"{ "compilerOptions": { "paths": { "~/*": ["lib/*"] } } }"#,
+    );
+    assert!(load().is_empty());
+
+    build_and_save();
+    // a run that only uses cached modules keeps what it was built with
+    build_and_save();
+    assert!(!load().is_empty());
+    write(
+      "node_modules/pkg/package.json",
+      r#"{ "name": "pkg", "exports": { "./feature": "./other.js" } }"#,
+    );
+    assert!(load().is_empty());
+  }
+}
@@ -1,22 +1,67 @@
 use crate::error::CoreError;
 use crate::file::process_package_json;
-use crate::module::{Asset, Dependency, Location, Module, ModuleKind, RootModule};
+use crate::module::{Asset, Dependency, DependencyKind, Location, Module, ModuleKind, RootModule};
+use crate::package_exports::{
+  resolve_exports, resolve_imports, split_package_specifier, ExportsTarget,
+};
 use crate::parser::Import::NodeDependency;
 use crate::parser::{Import, UnresolvedImport};
+use crate::tsconfig::TsConfig;
 use nom::error::dbg_dmp;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::OpenOptions;
 use std::io::{Error, Read};
 use std::iter::FromIterator;
 use std::ops::{Deref, DerefMut};
 use std::path::{Iter, Path, PathBuf};
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
 enum SearchSpace {
   NodeModule(PathBuf),
   RelativePath(PathBuf),
   IncludedPath(PathBuf),
+  // where a specifier points to after it was mapped by configuration, like tsconfig paths
+  Aliased(PathBuf),
+  // the specifier was aliased to `false`, it is replaced with an empty module
+  Ignored(PathBuf),
+}
+
+/// An entry of webpack's `resolve.alias`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alias {
+  pub name: String,
+  /// Only `name` itself is aliased and not `name/sub/path`, the `$` suffix in webpack
+  pub exact: bool,
+  /// Tried in order until one resolves
+  pub targets: Vec<AliasTarget>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AliasTarget {
+  /// An absolute path
+  Path(PathBuf),
+  /// Another module request, resolved like any other specifier
+  Module(String),
+  /// `false`, the module is left out of the bundle
+  Ignored,
+}
+
+impl Alias {
+  /// What is left of the specifier after the alias name if it matches
+  fn strip<'a>(&self, specifier: &'a str) -> Option<&'a str> {
+    let remainder = specifier.strip_prefix(self.name.as_str())?;
+    if remainder.is_empty() {
+      Some(remainder)
+    } else if !self.exact && remainder.starts_with('/') {
+      Some(&remainder[1..])
+    } else {
+      None
+    }
+  }
 }
 
 impl Deref for SearchSpace {
@@ -27,6 +72,8 @@ impl Deref for SearchSpace {
       SearchSpace::NodeModule(p) => p,
       SearchSpace::RelativePath(p) => p,
       SearchSpace::IncludedPath(p) => p,
+      SearchSpace::Aliased(p) => p,
+      SearchSpace::Ignored(p) => p,
     }
   }
 }
@@ -36,11 +83,20 @@ impl Default for Resolver {
     Self {
       recursively_resolve_node_modules: false,
       resolve_root: Default::default(),
-      extensions: ["jsx", "js", "ts", "tsx"]
+      extensions: ["jsx", "js", "mjs", "cjs", "ts", "tsx"]
         .iter()
         .map(|str| str.to_string())
         .collect(),
       included_directories: vec![],
+      module_directories: vec!["node_modules".to_string()],
+      aliases: vec![],
+      tsconfig: None,
+      conditions: ["webpack", "browser", "module"]
+        .iter()
+        .map(|str| str.to_string())
+        .collect(),
+      tsconfigs: Default::default(),
+      package_jsons: Default::default(),
     }
   }
 }
@@ -50,6 +106,20 @@ pub struct Resolver {
   pub resolve_root: Location,
   pub extensions: HashSet<String>,
   pub included_directories: Vec<PathBuf>,
+  // Directory names looked up in every ancestor of the importing file, webpack's
+  // `resolve.modules` without the paths
+  pub module_directories: Vec<String>,
+  // Checked before anything else, the first matching alias is used
+  pub aliases: Vec<Alias>,
+  // Used for files that don't have a tsconfig.json or jsconfig.json of their own between them
+  // and the resolve root.
+  pub tsconfig: Option<PathBuf>,
+  // Conditions matched against package.json `exports` and `imports`. `default` always matches,
+  // `import` or `require` is added depending on how the module is imported.
+  pub conditions: Vec<String>,
+  // The closest config for every directory we have resolved an import from
+  tsconfigs: RwLock<HashMap<PathBuf, Option<Arc<TsConfig>>>>,
+  package_jsons: RwLock<HashMap<PathBuf, Option<Arc<serde_json::Value>>>>,
 }
 
 impl Resolver {
@@ -75,12 +145,12 @@ impl Resolver {
             .map(|(k, v)| {
               if let Some(value) = v.as_str() {
                 if value.starts_with("file:") {
-                  UnresolvedImport(NodeDependency(PathBuf::from(value.replace("file:", ""))))
+                  UnresolvedImport::from(NodeDependency(PathBuf::from(value.replace("file:", ""))))
                 } else {
-                  UnresolvedImport(NodeDependency(PathBuf::from(k)))
+                  UnresolvedImport::from(NodeDependency(PathBuf::from(k)))
                 }
               } else {
-                UnresolvedImport(NodeDependency(PathBuf::from(k)))
+                UnresolvedImport::from(NodeDependency(PathBuf::from(k)))
               }
             })
             .map(|unresolved| self.resolve_asset(&package_json_location, &unresolved))
@@ -92,6 +162,81 @@ impl Resolver {
     }
   }
 
+  /// The file a package resolves to when it is imported without a subpath, `kind` picks between
+  /// the `import` and `require` conditions of its exports
+  pub fn package_entrypoint(
+    &self,
+    package_directory: &Path,
+    package_json_value: &serde_json::Value,
+    kind: DependencyKind,
+  ) -> PathBuf {
+    package_json_value
+      .get("exports")
+      .filter(|exports| !exports.is_null())
+      .and_then(|exports| {
+        resolve_exports(package_directory, exports, ".", &self.conditions_for(kind))
+      })
+      .unwrap_or_else(|| {
+        let main_file = package_json_value["module"]
+          .as_str()
+          .or(package_json_value["main"].as_str())
+          .unwrap_or("index.js");
+        package_directory.join(main_file)
+      })
+  }
+
+  fn conditions_for(&self, kind: DependencyKind) -> Vec<&str> {
+    let mut conditions: Vec<&str> = self.conditions.iter().map(|c| c.as_str()).collect();
+    match kind {
+      DependencyKind::Require => conditions.push("require"),
+      _ => conditions.push("import"),
+    }
+    conditions
+  }
+
+  /// Every tsconfig.json and package.json read so far to resolve imports, a change to any of
+  /// them can change where an import ends up
+  pub(crate) fn configuration_files(&self) -> Vec<PathBuf> {
+    let tsconfigs = self.tsconfigs.read();
+    let package_jsons = self.package_jsons.read();
+    let mut files: Vec<PathBuf> = tsconfigs
+      .values()
+      .flatten()
+      .flat_map(|config| config.files.iter().cloned())
+      .chain(
+        package_jsons
+          .iter()
+          .filter(|(_, value)| value.is_some())
+          .map(|(path, _)| path.clone()),
+      )
+      .collect();
+    files.sort();
+    files.dedup();
+    files
+  }
+
+  fn read_package_json(&self, path: &Path) -> Option<Arc<serde_json::Value>> {
+    if let Some(value) = self.package_jsons.read().get(path) {
+      return value.clone();
+    }
+
+    let value = std::fs::read_to_string(path)
+      .ok()
+      .and_then(|contents| match serde_json::from_str(&contents) {
+        Ok(value) => Some(Arc::new(value)),
+        Err(e) => {
+          tracing::warn!("Ignoring {:?}, it is not valid json: {}", path, e);
+          None
+        }
+      });
+
+    self
+      .package_jsons
+      .write()
+      .insert(path.to_path_buf(), value.clone());
+    value
+  }
+
   pub fn resolve_normal_module(
     &self,
     location: &Location,
@@ -119,7 +264,10 @@ impl Resolver {
     let mut output_asset = Asset::Unresolved(unresolved_dependency.as_ref().to_path_buf());
     for path in search_space {
       // try to resolve it as a file first
-      if let Some(asset) = self.resolve_file(&path).or(self.resolve_directory(&path)) {
+      if let Some(asset) = self
+        .resolve_file(&path)
+        .or(self.resolve_directory(&path, dependency_kind(unresolved_dependency.import_kind())))
+      {
         output_asset = asset;
         break;
       }
@@ -127,12 +275,14 @@ impl Resolver {
 
     tracing::debug!("Resolved asset: {:?}", &output_asset);
 
+    let spans = unresolved_dependency.spans;
+    let bindings = unresolved_dependency.bindings.clone();
     match unresolved_dependency.import_kind() {
-      Import::Require(_) => Dependency::Require(output_asset),
-      Import::AsyncImport(_) => Dependency::AsyncImport(output_asset),
-      Import::ExportFrom(_) => Dependency::Import(output_asset),
-      Import::Import(_) => Dependency::Import(output_asset),
-      Import::NodeDependency(_) => Dependency::Import(output_asset),
+      Import::Require(_) => Dependency::Require(output_asset, spans, bindings),
+      Import::AsyncImport(_) => Dependency::AsyncImport(output_asset, spans, bindings),
+      Import::ExportFrom(_) => Dependency::Import(output_asset, spans, bindings),
+      Import::Import(_) => Dependency::Import(output_asset, spans, bindings),
+      Import::NodeDependency(_) => Dependency::Import(output_asset, spans, bindings),
     }
   }
 
@@ -151,7 +301,7 @@ impl Resolver {
   }
 
   #[tracing::instrument(skip(self))]
-  fn resolve_directory(&self, search_space: &SearchSpace) -> Option<Asset> {
+  fn resolve_directory(&self, search_space: &SearchSpace, kind: DependencyKind) -> Option<Asset> {
     tracing::trace!("Resolving directory");
     if !search_space.is_dir() {
       return None;
@@ -162,7 +312,7 @@ impl Resolver {
       SearchSpace::NodeModule(path) => {
         if let Some(package_json_file) = self.find_closest_package_json(&path) {
           let location = Location::new(&**search_space).unwrap();
-          let main_location = process_package_json(&self, &package_json_file)
+          let main_location = process_package_json(&self, &package_json_file, kind)
             .map(|m| m.location)
             .ok();
           let mut file_in_directory = None;
@@ -189,7 +339,10 @@ impl Resolver {
           });
         }
       }
-      SearchSpace::RelativePath(path) | SearchSpace::IncludedPath(path) => {
+      SearchSpace::Ignored(_) => {}
+      SearchSpace::RelativePath(path)
+      | SearchSpace::IncludedPath(path)
+      | SearchSpace::Aliased(path) => {
         for extension in self.extensions.iter() {
           let file_name = Path::new("index").with_extension(extension);
           let file_path = path.join(file_name);
@@ -206,6 +359,10 @@ impl Resolver {
   #[tracing::instrument(skip(self))]
   fn resolve_file(&self, search_space: &SearchSpace) -> Option<Asset> {
     tracing::trace!("Resolving file");
+    if let SearchSpace::Ignored(specifier) = search_space {
+      return Some(Asset::Ignored(specifier.clone()));
+    }
+
     // Files like .gif, .svg, .css etc
     if search_space.is_file()
       && !self.extensions.contains(
@@ -231,9 +388,10 @@ impl Resolver {
               });
             }
           }
-          SearchSpace::RelativePath(path) | SearchSpace::IncludedPath(path) => {
+          SearchSpace::RelativePath(_) | SearchSpace::IncludedPath(_) | SearchSpace::Aliased(_) => {
             return Some(Asset::Module(Location::new(file).unwrap()))
           }
+          SearchSpace::Ignored(_) => {}
         }
       }
     }
@@ -241,10 +399,94 @@ impl Resolver {
     None
   }
 
+  /// The tsconfig.json or jsconfig.json closest to a file, so every package in a workspace can
+  /// have its own paths. Files in node_modules never use them.
+  fn tsconfig_for(&self, location: &Location) -> Option<Arc<TsConfig>> {
+    let directory = location.as_ref().parent()?;
+    if let Some(config) = self.tsconfigs.read().get(directory) {
+      return config.clone();
+    }
+
+    let config = if directory
+      .components()
+      .any(|c| c.as_os_str() == "node_modules")
+    {
+      None
+    } else {
+      directory
+        .ancestors()
+        .take_while(|ancestor| ancestor.starts_with(&self.resolve_root))
+        .flat_map(|ancestor| {
+          TsConfig::FILE_NAMES
+            .iter()
+            .map(move |name| ancestor.join(name))
+        })
+        .chain(self.tsconfig.clone())
+        .find(|path| path.is_file())
+        .and_then(|path| match TsConfig::load(&path) {
+          Ok(config) => Some(Arc::new(config)),
+          Err(e) => {
+            tracing::warn!("Ignoring {:?}, it could not be loaded: {}", &path, e);
+            None
+          }
+        })
+    };
+
+    self
+      .tsconfigs
+      .write()
+      .insert(directory.to_path_buf(), config.clone());
+    config
+  }
+
   fn create_search_space<'a, 'b>(
     &'a self,
     location: &'a Location,
     target_path: &'b UnresolvedImport,
+  ) -> Box<dyn Iterator<Item = SearchSpace> + 'a> {
+    let specifier = target_path.as_ref().to_string_lossy();
+    let alias = self
+      .aliases
+      .iter()
+      .find_map(|alias| alias.strip(&specifier).map(|remainder| (alias, remainder)));
+
+    match alias {
+      Some((alias, remainder)) => {
+        let import = target_path.clone();
+        let remainder = remainder.to_string();
+        Box::new(alias.targets.iter().flat_map(
+          move |target| -> Box<dyn Iterator<Item = SearchSpace> + 'a> {
+            match target {
+              AliasTarget::Ignored => Box::new(std::iter::once(SearchSpace::Ignored(
+                import.as_ref().to_path_buf(),
+              ))),
+              AliasTarget::Path(path) if remainder.is_empty() => {
+                Box::new(std::iter::once(SearchSpace::Aliased(path.clone())))
+              }
+              AliasTarget::Path(path) => {
+                Box::new(std::iter::once(SearchSpace::Aliased(path.join(&remainder))))
+              }
+              // aliases are only applied once, so `react: "preact/compat"` can't loop
+              AliasTarget::Module(module) => {
+                let module = if remainder.is_empty() {
+                  module.clone()
+                } else {
+                  format!("{}/{}", module, remainder)
+                };
+                self.create_unaliased_search_space(location, &import.with_path(module))
+              }
+            }
+          },
+        ))
+      }
+      None => self.create_unaliased_search_space(location, target_path),
+    }
+  }
+
+  fn create_unaliased_search_space<'a, 'b>(
+    &'a self,
+    location: &'a Location,
+    target_path: &'b UnresolvedImport,
   ) -> Box<dyn Iterator<Item = SearchSpace> + 'a> {
     if target_path.as_ref().is_absolute() {
       return Box::new(std::iter::once(SearchSpace::RelativePath(
@@ -252,6 +494,11 @@ impl Resolver {
       )));
     }
 
+    let specifier = target_path.as_ref().to_string_lossy().to_string();
+    if specifier.starts_with('#') {
+      return self.create_imports_search_space(location, target_path);
+    }
+
     let iterator = std::iter::empty();
 
     // the single relative path like "a/b.js"
@@ -269,6 +516,17 @@ impl Resolver {
       )))
     };
 
+    // tsconfig paths and baseUrl only apply to bare specifiers
+    let aliased_paths = if specifier.starts_with('.') {
+      vec![]
+    } else {
+      self
+        .tsconfig_for(location)
+        .map(|config| config.candidate_paths(&specifier))
+        .unwrap_or_default()
+    };
+    let iterator = iterator.chain(aliased_paths.into_iter().map(SearchSpace::Aliased));
+
     // all of the possible included directories
     let resolve_root = self.resolve_root.clone();
     let next_target_path = target_path.clone();
@@ -287,25 +545,95 @@ impl Resolver {
       )
     }));
 
-    // all of the possible node modules
+    // all of the possible node modules. Packages with `exports` only expose what is in there
+    let conditions = self.conditions_for(dependency_kind(target_path.import_kind()));
+    let package =
+      split_package_specifier(&specifier).map(|(name, subpath)| (name.to_string(), subpath));
     let target_path = target_path.as_ref().to_path_buf();
     let iterator = iterator.chain(
       location
         .as_ref()
         .ancestors()
         .skip(1)
-        .map(move |anc| SearchSpace::NodeModule(anc.join("node_modules").join(&target_path))),
+        .flat_map(move |anc| {
+          self
+            .module_directories
+            .iter()
+            .map(move |directory| anc.join(directory))
+        })
+        .filter_map(move |node_modules| {
+          if let Some((name, subpath)) = &package {
+            let package_directory = node_modules.join(name);
+            let package_json = self.read_package_json(&package_directory.join("package.json"));
+            if let Some(exports) = package_json
+              .as_ref()
+              .and_then(|value| value.get("exports"))
+              .filter(|exports| !exports.is_null())
+            {
+              return resolve_exports(&package_directory, exports, subpath, &conditions)
+                .map(SearchSpace::NodeModule);
+            }
+          }
+          Some(SearchSpace::NodeModule(node_modules.join(&target_path)))
+        }),
     );
 
     Box::new(iterator)
   }
+
+  /// `#specifiers` are private to a package and mapped through the `imports` of the package.json
+  /// closest to the importing file
+  fn create_imports_search_space<'a>(
+    &'a self,
+    location: &'a Location,
+    target_path: &UnresolvedImport,
+  ) -> Box<dyn Iterator<Item = SearchSpace> + 'a> {
+    let specifier = target_path.as_ref().to_string_lossy();
+    let target = location
+      .as_ref()
+      .ancestors()
+      .map(|ancestor| ancestor.join("package.json"))
+      .find(|package_json| package_json.is_file())
+      .and_then(|package_json| {
+        let value = self.read_package_json(&package_json)?;
+        resolve_imports(
+          package_json.parent()?,
+          value.get("imports")?,
+          &specifier,
+          &self.conditions_for(dependency_kind(target_path.import_kind())),
+        )
+      });
+
+    match target {
+      Some(ExportsTarget::Path(path))
+        if path.components().any(|c| c.as_os_str() == "node_modules") =>
+      {
+        Box::new(std::iter::once(SearchSpace::NodeModule(path)))
+      }
+      Some(ExportsTarget::Path(path)) => Box::new(std::iter::once(SearchSpace::Aliased(path))),
+      Some(ExportsTarget::Package(package)) => {
+        let import = target_path.with_path(package);
+        self.create_unaliased_search_space(location, &import)
+      }
+      None => Box::new(std::iter::empty()),
+    }
+  }
+}
+
+fn dependency_kind(import: &Import) -> DependencyKind {
+  match import {
+    Import::Require(_) => DependencyKind::Require,
+    Import::AsyncImport(_) => DependencyKind::AsyncImport,
+    Import::ExportFrom(_) | Import::Import(_) | Import::NodeDependency(_) => DependencyKind::Import,
+  }
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::module::Location;
-  use crate::parser::Import::Import;
-  use crate::parser::UnresolvedImport;
+  use crate::file::process_package_json;
+  use crate::module::{DependencyKind, Location};
+  use crate::parser::Import::{Import, Require};
+  use crate::parser::{ImportedBindings, NamedBinding, UnresolvedImport};
   use crate::resolve::Resolver;
   use std::path::{Path, PathBuf};
 
@@ -330,6 +658,106 @@ mod tests {
     }
   }
 
+  #[test]
+  fn it_resolves_paths_from_the_closest_tsconfig() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_typescript");
+    let resolve = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+
+    let app = Location::new(root.join("packages/app/src/index.ts")).unwrap();
+    let state = resolve.resolve_asset(
+      &app,
+      &UnresolvedImport::new_from_import(Import("~/state".into())),
+    );
+    assert_eq!(
+      state.location(),
+      Location::new(root.join("packages/app/src/state/index.ts")).ok()
+    );
+    let format = resolve.resolve_asset(
+      &app,
+      &UnresolvedImport::new_from_import(Import("@shared/format".into())),
+    );
+    assert_eq!(
+      format.location(),
+      Location::new(root.join("packages/shared/src/format.ts")).ok()
+    );
+
+    // the same alias means something else in the shared package
+    let shared = Location::new(root.join("packages/shared/src/index.ts")).unwrap();
+    let format = resolve.resolve_asset(
+      &shared,
+      &UnresolvedImport::new_from_import(Import("~/format".into())),
+    );
+    assert_eq!(
+      format.location(),
+      Location::new(root.join("packages/shared/src/format.ts")).ok()
+    );
+  }
+
+  #[test]
+  fn it_resolves_through_package_exports_and_imports() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_exports");
+    let resolve = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    let index = Location::new(root.join("index.js")).unwrap();
+    let package = root.join("node_modules/@scope/pkg");
+
+    let resolve_import =
+      |import| resolve.resolve_asset(&index, &UnresolvedImport::new_from_import(import));
+    assert_eq!(
+      resolve_import(Import("@scope/pkg".into())).location(),
+      Location::new(package.join("esm/index.mjs")).ok()
+    );
+    assert_eq!(
+      resolve_import(Require("@scope/pkg".into())).location(),
+      Location::new(package.join("cjs/index.js")).ok()
+    );
+    assert_eq!(
+      resolve_import(Import("@scope/pkg/feature".into())).location(),
+      Location::new(package.join("esm/feature/index.mjs")).ok()
+    );
+    // not exported, even though the file is there
+    assert_eq!(
+      resolve_import(Import("@scope/pkg/esm/internal".into())).location(),
+      None
+    );
+    assert_eq!(
+      resolve_import(Import("#utils/format".into())).location(),
+      Location::new(root.join("src/utils/format.js")).ok()
+    );
+
+    // imports inside of the package are private to it
+    let from_package = Location::new(package.join("esm/index.mjs")).unwrap();
+    let internal = resolve.resolve_asset(
+      &from_package,
+      &UnresolvedImport::new_from_import(Import("#internal".into())),
+    );
+    assert_eq!(
+      internal.location(),
+      Location::new(package.join("esm/internal.mjs")).ok()
+    );
+  }
+
+  #[test]
+  fn it_picks_the_package_entrypoint_for_the_kind_of_import() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_exports");
+    let resolve = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    let package_json = Location::new(root.join("node_modules/@scope/pkg/package.json")).unwrap();
+    let package = root.join("node_modules/@scope/pkg");
+
+    let entrypoint = |kind| {
+      process_package_json(&resolve, &package_json, kind)
+        .unwrap()
+        .location
+    };
+    assert_eq!(
+      entrypoint(DependencyKind::Import),
+      Location::new(package.join("esm/index.mjs")).unwrap()
+    );
+    assert_eq!(
+      entrypoint(DependencyKind::Require),
+      Location::new(package.join("cjs/index.js")).unwrap()
+    );
+  }
+
   #[test]
   fn it_can_resolve_a_bunch_of_dependencies() {
     let location = Location::new_from_path_buf(
@@ -356,4 +784,34 @@ mod tests {
       dbg!(resolved_dependency);
     }
   }
+
+  #[test]
+  fn it_keeps_where_an_import_is_and_what_it_binds() {
+    let root =
+      Location::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript")).unwrap();
+    let location = Location::new(root.as_ref().join("module/a.js")).unwrap();
+    let resolve = Resolver::new(&root, vec![]);
+
+    let unresolved = UnresolvedImport::parse_many("\nimport { b as c } from './b';").unwrap();
+    let module = resolve.resolve_normal_module(&location, &unresolved);
+
+    let dependency = &module.dependencies[0];
+    assert_eq!(
+      dependency.location(),
+      Some(Location::new(root.as_ref().join("module/b.js")).unwrap())
+    );
+    assert_eq!(dependency.spans(), unresolved[0].spans);
+    assert_eq!(dependency.spans().unwrap().specifier.start.line, 2);
+    assert_eq!(
+      *dependency.bindings(),
+      ImportedBindings::Import {
+        default: None,
+        namespace: None,
+        named: vec![NamedBinding {
+          name: "b".to_string(),
+          alias: Some("c".to_string()),
+        }],
+      }
+    );
+  }
 }
@@ -0,0 +1,3 @@
+import { Form } from './index'
+
+export const Button = () => Form
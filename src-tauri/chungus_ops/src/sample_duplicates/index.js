@@ -0,0 +1,2 @@
+import { isElement } from 'react-is'
+import widget from 'widget'
@@ -0,0 +1,5 @@
+import pkg from "@scope/pkg";
+import { feature } from "@scope/pkg/feature";
+import { format } from "#utils/format";
+
+export default () => format(pkg, feature);
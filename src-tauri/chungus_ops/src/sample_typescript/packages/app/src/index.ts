@@ -0,0 +1,4 @@
+import { store } from "~/state";
+import { format } from "@shared/format";
+
+export const app = () => format(store);
@@ -0,0 +1 @@
+export const store = { count: 0 };
@@ -0,0 +1 @@
+export const format = (value: unknown) => JSON.stringify(value);
@@ -0,0 +1,5 @@
+import { Button } from "components/Button";
+import fs from "fs";
+import legacy from "legacy";
+
+export default () => Button(legacy, fs);
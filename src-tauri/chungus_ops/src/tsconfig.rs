@@ -0,0 +1,352 @@
+use crate::error::CoreError;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// The subset of tsconfig.json / jsconfig.json that changes how imports resolve.
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct TsConfigRaw {
+  #[serde(default)]
+  extends: Option<Extends>,
+  #[serde(default)]
+  compiler_options: CompilerOptionsRaw,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Extends {
+  Single(String),
+  Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct CompilerOptionsRaw {
+  base_url: Option<String>,
+  paths: Option<BTreeMap<String, Vec<String>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathMapping {
+  pub pattern: String,
+  pub targets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TsConfig {
+  /// Absolute `compilerOptions.baseUrl`
+  pub base_url: Option<PathBuf>,
+  pub paths: Vec<PathMapping>,
+  // `paths` targets are relative to the baseUrl, or to the config that declared them when there
+  // isn't one
+  paths_directory: PathBuf,
+  // the config and every config it extends
+  pub(crate) files: Vec<PathBuf>,
+}
+
+impl TsConfig {
+  pub const FILE_NAMES: [&'static str; 2] = ["tsconfig.json", "jsconfig.json"];
+
+  /// Loads a config and everything it `extends`. Options in a config override the ones it
+  /// extends, and relative paths stay relative to the file they were written in.
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+    let mut config = Self::default();
+    config.merge_from(path.as_ref(), &mut HashSet::new())?;
+    Ok(config)
+  }
+
+  // `ancestors` are the configs that extend this one, a config that several of them extend is
+  // merged once for each
+  fn merge_from(&mut self, path: &Path, ancestors: &mut HashSet<PathBuf>) -> Result<(), CoreError> {
+    let path = &path.canonicalize()?;
+    if ancestors.contains(path) {
+      return Err(CoreError::custom(&format!(
+        "tsconfig {:?} extends itself",
+        path
+      )));
+    }
+
+    let contents = std::fs::read_to_string(path)?;
+    self.files.push(path.to_path_buf());
+    let raw: TsConfigRaw = serde_json::from_str(&strip_json_comments(&contents))?;
+    let directory = path.parent().unwrap_or_else(|| Path::new("/"));
+
+    let parents = match raw.extends {
+      None => vec![],
+      Some(Extends::Single(parent)) => vec![parent],
+      Some(Extends::Many(parents)) => parents,
+    };
+    ancestors.insert(path.to_path_buf());
+    for parent in parents {
+      let parent_path = resolve_extends(directory, &parent).ok_or_else(|| {
+        CoreError::custom(&format!(
+          "Could not find {:?} extended by {:?}",
+          parent, path
+        ))
+      })?;
+      self.merge_from(&parent_path, ancestors)?;
+    }
+    ancestors.remove(path);
+
+    if let Some(base_url) = raw.compiler_options.base_url {
+      self.base_url = Some(directory.join(base_url));
+    }
+    if let Some(paths) = raw.compiler_options.paths {
+      self.paths = paths
+        .into_iter()
+        .map(|(pattern, targets)| PathMapping { pattern, targets })
+        .collect();
+      self.paths_directory = directory.to_path_buf();
+    }
+
+    Ok(())
+  }
+
+  /// Candidate paths for a non relative specifier, in the order typescript tries them. Only the
+  /// most specific matching `paths` pattern is used, followed by the baseUrl.
+  pub fn candidate_paths(&self, specifier: &str) -> Vec<PathBuf> {
+    let paths_root = self.base_url.as_ref().unwrap_or(&self.paths_directory);
+    let mut best_match: Option<(&PathMapping, &str, usize)> = None;
+
+    for mapping in self.paths.iter() {
+      let (prefix, suffix) = match mapping.pattern.find('*') {
+        Some(star) => (&mapping.pattern[..star], &mapping.pattern[star + 1..]),
+        None if mapping.pattern == specifier => {
+          best_match = Some((mapping, "", usize::MAX));
+          break;
+        }
+        None => continue,
+      };
+
+      let matches = specifier.len() >= prefix.len() + suffix.len()
+        && specifier.starts_with(prefix)
+        && specifier.ends_with(suffix);
+      let is_better = best_match
+        .map(|(_, _, length)| prefix.len() > length)
+        .unwrap_or(true);
+
+      if matches && is_better {
+        let wildcard = &specifier[prefix.len()..specifier.len() - suffix.len()];
+        best_match = Some((mapping, wildcard, prefix.len()));
+      }
+    }
+
+    let mut candidates: Vec<PathBuf> = best_match
+      .map(|(mapping, wildcard, _)| {
+        mapping
+          .targets
+          .iter()
+          .map(|target| paths_root.join(target.replacen('*', wildcard, 1)))
+          .collect()
+      })
+      .unwrap_or_default();
+
+    if let Some(base_url) = &self.base_url {
+      candidates.push(base_url.join(specifier));
+    }
+
+    candidates
+  }
+}
+
+fn resolve_extends(directory: &Path, extends: &str) -> Option<PathBuf> {
+  let with_json = |path: PathBuf| {
+    if path.is_file() {
+      Some(path)
+    } else {
+      let mut file_name = path.file_name()?.to_os_string();
+      file_name.push(".json");
+      Some(path.with_file_name(file_name)).filter(|path| path.is_file())
+    }
+  };
+
+  if extends.starts_with('.') || Path::new(extends).is_absolute() {
+    return with_json(directory.join(extends));
+  }
+
+  // a config published as a package, like @tsconfig/node16
+  directory.ancestors().find_map(|ancestor| {
+    let package = ancestor.join("node_modules").join(extends);
+    with_json(package.clone())
+      .or_else(|| Some(package.join("tsconfig.json")).filter(|p| p.is_file()))
+  })
+}
+
+/// tsconfig files are allowed to have comments and trailing commas
+pub fn strip_json_comments(contents: &str) -> String {
+  let mut output = String::with_capacity(contents.len());
+  let mut chars = contents.chars().peekable();
+  // where in the output the last comma went, in case it turns out to be a trailing one
+  let mut pending_comma: Option<usize> = None;
+
+  while let Some(c) = chars.next() {
+    match c {
+      '"' => {
+        pending_comma = None;
+        output.push(c);
+        while let Some(c) = chars.next() {
+          output.push(c);
+          match c {
+            '\\' => output.extend(chars.next()),
+            '"' => break,
+            _ => {}
+          }
+        }
+      }
+      '/' if chars.peek() == Some(&'/') => {
+        for c in &mut chars {
+          if c == '\n' {
+            output.push(c);
+            break;
+          }
+        }
+      }
+      '/' if chars.peek() == Some(&'*') => {
+        chars.next();
+        let mut previous = ' ';
+        for c in &mut chars {
+          if previous == '*' && c == '/' {
+            break;
+          }
+          previous = c;
+        }
+      }
+      ',' => {
+        pending_comma = Some(output.len());
+        output.push(c);
+      }
+      '}' | ']' => {
+        if let Some(comma) = pending_comma.take() {
+          output.replace_range(comma..comma + 1, " ");
+        }
+        output.push(c);
+      }
+      c if c.is_whitespace() => output.push(c),
+      c => {
+        pending_comma = None;
+        output.push(c);
+      }
+    }
+  }
+
+  output
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_strips_comments_and_trailing_commas() {
+    let source = r#"{
+      // the base
+      "extends": "./base", /* block */
+      "compilerOptions": {
+        "paths": { "~/*": ["src/*",], },
+        "url": "http://not-a-comment", // trailing
+      },
+    }"#;
+
+    let value: serde_json::Value = serde_json::from_str(&strip_json_comments(source)).unwrap();
+    assert_eq!(value["extends"], "./base");
+    assert_eq!(value["compilerOptions"]["paths"]["~/*"][0], "src/*");
+    assert_eq!(value["compilerOptions"]["url"], "http://not-a-comment");
+  }
+
+  #[test]
+  fn it_picks_the_most_specific_pattern() {
+    let config = TsConfig {
+      base_url: Some(PathBuf::from("/project")),
+      paths: vec![
+        PathMapping {
+          pattern: "@app/*".into(),
+          targets: vec!["src/*".into(), "generated/*".into()],
+        },
+        PathMapping {
+          pattern: "@app/components/*".into(),
+          targets: vec!["ui/*".into()],
+        },
+        PathMapping {
+          pattern: "config".into(),
+          targets: vec!["config/index.ts".into()],
+        },
+      ],
+      paths_directory: PathBuf::from("/ignored"),
+      files: vec![],
+    };
+
+    assert_eq!(
+      config.candidate_paths("@app/state/store"),
+      vec![
+        PathBuf::from("/project/src/state/store"),
+        PathBuf::from("/project/generated/state/store"),
+        PathBuf::from("/project/@app/state/store"),
+      ]
+    );
+    assert_eq!(
+      config.candidate_paths("@app/components/Button")[0],
+      PathBuf::from("/project/ui/Button")
+    );
+    assert_eq!(
+      config.candidate_paths("config")[0],
+      PathBuf::from("/project/config/index.ts")
+    );
+    assert_eq!(
+      config.candidate_paths("react"),
+      vec![PathBuf::from("/project/react")]
+    );
+  }
+
+  #[test]
+  fn it_follows_extends_chains() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_typescript");
+    let config = TsConfig::load(directory.join("packages/app/tsconfig.json")).unwrap();
+
+    // baseUrl comes from the base config, relative to where it was written
+    assert_eq!(config.base_url, Some(directory.clone()));
+    assert_eq!(
+      config.files,
+      vec![
+        directory.join("packages/app/tsconfig.json"),
+        directory.join("tsconfig.base.json")
+      ]
+    );
+    // paths are overridden by the package
+    assert_eq!(
+      config.candidate_paths("~/state")[0],
+      directory.join("packages/app/src/state")
+    );
+  }
+
+  #[test]
+  fn it_merges_a_config_extended_twice() {
+    let directory = tempfile::tempdir().unwrap();
+    let write =
+      |path: &str, contents: &str| std::fs::write(directory.path().join(path), contents).unwrap();
+    write(
+      "base.json",
+      r#"{ "compilerOptions": { "baseUrl": "src" } }"#,
+    );
+    write(
+      "a.json",
+      r#"{ "extends": "./base.json", "compilerOptions": { "paths": { "~/*": ["a/*"] } } }"#,
+    );
+    write("b.json", r#"{ "extends": "./base.json" }"#);
+    write(
+      "tsconfig.json",
+      r#"{ "extends": ["./a.json", "./b.json"] }"#,
+    );
+    write("loop.json", r#"{ "extends": "./loop.json" }"#);
+
+    let root = directory.path().canonicalize().unwrap();
+    let config = TsConfig::load(root.join("tsconfig.json")).unwrap();
+    assert_eq!(config.base_url, Some(root.join("src")));
+    assert_eq!(
+      config.candidate_paths("~/state")[0],
+      root.join("src/a/state")
+    );
+    assert!(TsConfig::load(root.join("loop.json")).is_err());
+  }
+}
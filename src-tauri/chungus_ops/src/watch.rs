@@ -0,0 +1,199 @@
+use std::collections::{BTreeSet, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::diagnostics::Diagnostics;
+use crate::error::CoreError;
+use crate::file::process_javascript_file;
+use crate::logging::ClientSideLogger;
+use crate::module::{Location, ModuleKind};
+use crate::resolve::Resolver;
+use crate::{recursively_build_dependency_tree, DependencyCache};
+
+/// Watches the folders of the modules in a dependency cache for files that change
+pub struct DependencyWatcher {
+  watcher: RecommendedWatcher,
+  events: Receiver<DebouncedEvent>,
+  directories: HashSet<PathBuf>,
+}
+
+impl DependencyWatcher {
+  /// A change is reported once its file has been left alone for `delay`, so an editor that saves
+  /// in several writes causes one update
+  pub fn new(delay: Duration) -> Result<Self, CoreError> {
+    let (sender, events) = channel();
+    Ok(Self {
+      watcher: notify::watcher(sender, delay)?,
+      events,
+      directories: HashSet::new(),
+    })
+  }
+
+  /// Starts watching the folder of every javascript module in `cache` that isn't watched yet.
+  /// Installed packages are left out, they only change when the lock file does.
+  #[tracing::instrument(skip(self, resolver, cache))]
+  pub fn watch_cache(
+    &mut self,
+    resolver: &Resolver,
+    cache: &DependencyCache,
+  ) -> Result<(), CoreError> {
+    let directories: BTreeSet<PathBuf> = cache
+      .iter()
+      .filter(|(_, module)| module.kind == ModuleKind::NormalModule)
+      .filter(|(location, _)| {
+        !location.as_ref().components().any(|component| {
+          resolver
+            .module_directories
+            .iter()
+            .any(|directory| component.as_os_str() == directory.as_str())
+        })
+      })
+      .filter_map(|(location, _)| {
+        location
+          .as_ref()
+          .parent()
+          .map(|parent| parent.to_path_buf())
+      })
+      .filter(|directory| !self.directories.contains(directory))
+      .collect();
+
+    for directory in directories {
+      self
+        .watcher
+        .watch(&directory, RecursiveMode::NonRecursive)?;
+      self.directories.insert(directory);
+    }
+    tracing::debug!("Watching {} folders", self.directories.len());
+    Ok(())
+  }
+
+  /// Waits up to `timeout` for files to change and returns every file that changed since, or
+  /// nothing when none did. When the watcher lost track of events every file in the watched
+  /// folders is returned. Errors once the watcher has stopped.
+  pub fn changes(&self, timeout: Duration) -> Result<Vec<PathBuf>, CoreError> {
+    let first = match self.events.recv_timeout(timeout) {
+      Ok(event) => event,
+      Err(RecvTimeoutError::Timeout) => return Ok(vec![]),
+      Err(RecvTimeoutError::Disconnected) => {
+        return Err(CoreError::custom("The file watcher stopped"))
+      }
+    };
+
+    let mut changed = BTreeSet::new();
+    for event in std::iter::once(first).chain(self.events.try_iter()) {
+      match event {
+        DebouncedEvent::Create(path)
+        | DebouncedEvent::Write(path)
+        | DebouncedEvent::Remove(path) => {
+          changed.insert(path);
+        }
+        DebouncedEvent::Rename(from, to) => {
+          changed.insert(from);
+          changed.insert(to);
+        }
+        DebouncedEvent::Error(e, path) => {
+          tracing::warn!("Error while watching {:?}: {}", path, e)
+        }
+        // events were dropped, so anything in the watched folders may have changed
+        DebouncedEvent::Rescan => {
+          tracing::info!("File events were missed, every watched module is parsed again");
+          for directory in self.directories.iter() {
+            match std::fs::read_dir(directory) {
+              Ok(entries) => changed.extend(entries.filter_map(|entry| Some(entry.ok()?.path()))),
+              Err(e) => tracing::warn!("Could not read {:?}: {}", directory, e),
+            }
+          }
+        }
+        DebouncedEvent::NoticeWrite(_)
+        | DebouncedEvent::NoticeRemove(_)
+        | DebouncedEvent::Chmod(_) => {}
+      }
+    }
+    Ok(changed.into_iter().collect())
+  }
+}
+
+/// Parses the javascript modules in `cache` at `changed` again, anything else in `changed` is
+/// skipped. What they import now that isn't in the cache yet is added to it, anything that
+/// couldn't be loaded goes into `diagnostics`. Returns the modules that were parsed again, to
+/// pass on to `Analysis::update`.
+///
+/// A file that can't be read anymore keeps its old module, whatever imported it is usually
+/// changed along with it.
+#[tracing::instrument(skip(resolver, cache, changed, diagnostics, logger))]
+pub fn update_dependency_cache(
+  resolver: &Resolver,
+  cache: &mut DependencyCache,
+  changed: &[PathBuf],
+  diagnostics: &mut Diagnostics,
+  logger: &impl ClientSideLogger,
+) -> Vec<Location> {
+  let mut updated = vec![];
+  for path in changed.iter().filter(|path| path.is_file()) {
+    let location = match Location::new(path) {
+      Ok(location) => location,
+      Err(_) => continue,
+    };
+    let is_javascript_module = cache
+      .get(&location)
+      .is_some_and(|module| module.kind == ModuleKind::NormalModule);
+    if !is_javascript_module || updated.contains(&location) {
+      continue;
+    }
+
+    logger.message(format!("Resolving module {:?}", &location));
+    let module = match process_javascript_file(resolver, &location) {
+      Ok(module) => module,
+      Err(e) => {
+        tracing::warn!("Could not parse {:?} again: {}", &location, e);
+        continue;
+      }
+    };
+    cache.insert(location.clone(), module.clone());
+    recursively_build_dependency_tree(cache, resolver, module, diagnostics, logger);
+    updated.push(location);
+  }
+
+  tracing::info!("Parsed {} changed modules again", updated.len());
+  updated
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::mpsc::Sender;
+
+  // a watcher whose events come from the test instead of the file system
+  fn watcher_with_events() -> (DependencyWatcher, Sender<DebouncedEvent>) {
+    let (sender, events) = channel();
+    let watcher = DependencyWatcher {
+      watcher: notify::watcher(channel().0, Duration::from_millis(10)).unwrap(),
+      events,
+      directories: HashSet::new(),
+    };
+    (watcher, sender)
+  }
+
+  #[test]
+  fn it_returns_every_watched_file_after_a_rescan() {
+    let directory = tempfile::tempdir().unwrap();
+    std::fs::write(directory.path().join("a.js"), "").unwrap();
+    std::fs::write(directory.path().join("b.js"), "").unwrap();
+
+    let (mut watcher, sender) = watcher_with_events();
+    watcher.directories.insert(directory.path().to_path_buf());
+    sender.send(DebouncedEvent::Rescan).unwrap();
+
+    assert_eq!(
+      watcher.changes(Duration::from_millis(10)).unwrap(),
+      vec![directory.path().join("a.js"), directory.path().join("b.js")]
+    );
+    assert!(watcher
+      .changes(Duration::from_millis(10))
+      .unwrap()
+      .is_empty());
+  }
+}
@@ -1,12 +1,19 @@
+use crate::compression::{compressed_file_sizes, CompressedSizes};
 use crate::error::CoreError;
 use crate::module::Location;
 use crate::resolve::Resolver;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
 #[derive(Debug)]
 pub struct WebpackReport {
   pub chunk_mapping: HashMap<Location, Vec<Chunk>>,
   pub chunk_id_map: HashMap<usize, Chunk>,
+  // bytes each module takes up in the emitted bundle, rollup, vite and esbuild metadata have them
+  pub module_sizes: HashMap<Location, usize>,
+  // webpack's size of each module, after loaders ran but before the bundle was minified
+  pub module_stat_sizes: HashMap<Location, usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +25,252 @@ pub struct Chunk {
   pub siblings: Vec<usize>,
   pub children: Vec<usize>,
   pub parsed_size: usize,
+  // emitted files, relative to the build output directory
+  #[serde(default)]
+  pub files: Vec<String>,
+  #[serde(default)]
+  pub gzip_size: Option<usize>,
+  #[serde(default)]
+  pub brotli_size: Option<usize>,
+}
+
+impl WebpackReport {
+  /// Compresses the files every chunk emitted to estimate what they cost over the wire. Source
+  /// maps are left out.
+  #[tracing::instrument(skip(self, output_directory))]
+  pub fn add_compressed_sizes(&mut self, output_directory: impl AsRef<Path>) {
+    let output_directory = output_directory.as_ref();
+
+    for chunk in self.chunk_id_map.values_mut() {
+      let sizes = chunk
+        .files
+        .iter()
+        .filter(|file| !file.ends_with(".map"))
+        .filter_map(|file| {
+          let path = output_directory.join(file);
+          match compressed_file_sizes(&path) {
+            Ok(sizes) => Some(sizes),
+            Err(e) => {
+              tracing::warn!("Could not compress {:?}: {}", &path, e);
+              None
+            }
+          }
+        })
+        .fold(None, |total: Option<CompressedSizes>, sizes| {
+          Some(total.unwrap_or_default() + sizes)
+        });
+
+      chunk.gzip_size = sizes.map(|sizes| sizes.gzip);
+      chunk.brotli_size = sizes.map(|sizes| sizes.brotli);
+    }
+
+    let chunk_id_map = &self.chunk_id_map;
+    for chunk in self.chunk_mapping.values_mut().flatten() {
+      if let Some(compressed) = chunk_id_map.get(&chunk.id) {
+        chunk.gzip_size = compressed.gzip_size;
+        chunk.brotli_size = compressed.brotli_size;
+      }
+    }
+  }
+}
+
+pub mod esbuild;
+pub mod rollup;
+
+/// Reads a webpack stats file, picking the parser from the webpack `version` it was written by.
+/// Stats without a version are told apart by their shape, like the `nameForCondition` of modules
+/// that only webpack 5 writes. Rollup and vite bundle metadata and esbuild metafiles are
+/// recognised by their shape too.
+#[tracing::instrument(skip(resolve, value))]
+pub fn create_report_from_reader(
+  resolve: &Resolver,
+  value: impl Read,
+) -> Result<WebpackReport, CoreError> {
+  let value: serde_json::Value = serde_json::from_reader(value)?;
+
+  if rollup::is_rollup_metadata(&value) {
+    return rollup::create_report_from_value(resolve, value);
+  }
+  if esbuild::is_esbuild_metafile(&value) {
+    return esbuild::create_report_from_value(resolve, value);
+  }
+
+  match major_version(&value) {
+    Some(version) if version >= 5 => v5::create_report_from_value(resolve, value),
+    Some(version) if version < 4 => Err(CoreError::custom(&format!(
+      "Stats from webpack {} are not supported",
+      version
+    ))),
+    Some(_) => v4::create_report_from_value(resolve, value),
+    None if is_webpack_5_shaped(&value) => v5::create_report_from_value(resolve, value),
+    None if is_webpack_4_shaped(&value) => v4::create_report_from_value(resolve, value),
+    None => Err(CoreError::custom(
+      "Could not tell which webpack version wrote the stats, write them with `version: true`",
+    )),
+  }
+}
+
+// Stats written with `all: false` have no version. Only webpack 5 gives chunks `idHints` and
+// modules a `type` or `nameForCondition`.
+fn is_webpack_5_shaped(value: &serde_json::Value) -> bool {
+  fn has_webpack_5_module(modules: &serde_json::Value) -> bool {
+    modules.as_array().is_some_and(|modules| {
+      modules.iter().any(|module| {
+        module.get("nameForCondition").is_some()
+          || module.get("type").is_some()
+          || has_webpack_5_module(&module["modules"])
+          || has_webpack_5_module(&module["children"])
+      })
+    })
+  }
+
+  let has_id_hints = value["chunks"]
+    .as_array()
+    .is_some_and(|chunks| chunks.iter().any(|chunk| chunk.get("idHints").is_some()));
+  let children = value["children"].as_array();
+  has_id_hints
+    || has_webpack_5_module(&value["modules"])
+    || children.is_some_and(|children| children.iter().any(is_webpack_5_shaped))
+}
+
+// Webpack 4 can't leave out the chunk fields its parser needs
+fn is_webpack_4_shaped(value: &serde_json::Value) -> bool {
+  match value["children"].as_array() {
+    Some(children) if !children.is_empty() => children.iter().all(is_webpack_4_shaped),
+    _ => value["modules"].is_array() && value["chunks"].is_array(),
+  }
+}
+
+// multi compiler stats only have a version on each child
+fn major_version(value: &serde_json::Value) -> Option<u64> {
+  let version = value["version"]
+    .as_str()
+    .or_else(|| value["children"][0]["version"].as_str())?;
+  version.split('.').next()?.parse().ok()
+}
+
+// A file written by a bundler that isn't webpack, and the modules that ended up in it
+#[derive(Debug, Default)]
+struct OutputChunk {
+  file_name: String,
+  // loaded by the page rather than imported by another chunk
+  is_entry: bool,
+  // static imports of other chunks, they are loaded together with this one
+  imports: Vec<String>,
+  dynamic_imports: Vec<String>,
+  // module ids with the number of bytes they take up in the chunk
+  modules: Vec<(String, usize)>,
+  // the size of the whole file when it is known, otherwise the sum of the modules
+  size: Option<usize>,
+}
+
+// Chunk ids are numbered in file name order so the same build always gets the same ids
+fn create_report_from_output_chunks(
+  resolve: &Resolver,
+  output_chunks: Vec<OutputChunk>,
+) -> WebpackReport {
+  let mut output_chunks = output_chunks;
+  output_chunks.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+  let ids: HashMap<&str, usize> = output_chunks
+    .iter()
+    .enumerate()
+    .map(|(id, chunk)| (chunk.file_name.as_str(), id))
+    .collect();
+  let ids_of = |file_names: &[String]| -> Vec<usize> {
+    file_names
+      .iter()
+      .filter_map(|file_name| ids.get(file_name.as_str()).copied())
+      .collect()
+  };
+
+  // chunks imported statically by an entry are loaded up front too
+  let mut initial: Vec<bool> = output_chunks.iter().map(|c| c.is_entry).collect();
+  let mut queue: Vec<usize> = (0..output_chunks.len()).filter(|id| initial[*id]).collect();
+  while let Some(id) = queue.pop() {
+    for import in ids_of(&output_chunks[id].imports) {
+      if !initial[import] {
+        initial[import] = true;
+        queue.push(import);
+      }
+    }
+  }
+
+  let mut chunk_id_map: HashMap<usize, Chunk> = HashMap::new();
+  for (id, output_chunk) in output_chunks.iter().enumerate() {
+    let name = Path::new(&output_chunk.file_name)
+      .file_stem()
+      .map(|stem| stem.to_string_lossy().to_string())
+      .unwrap_or_else(|| output_chunk.file_name.clone());
+    chunk_id_map.insert(
+      id,
+      Chunk {
+        id,
+        name,
+        initial: initial[id],
+        parents: vec![],
+        siblings: ids_of(&output_chunk.imports),
+        children: ids_of(&output_chunk.dynamic_imports),
+        parsed_size: output_chunk
+          .size
+          .unwrap_or_else(|| output_chunk.modules.iter().map(|(_, size)| size).sum()),
+        files: vec![output_chunk.file_name.clone()],
+        gzip_size: None,
+        brotli_size: None,
+      },
+    );
+  }
+  for (id, output_chunk) in output_chunks.iter().enumerate() {
+    for child in ids_of(&output_chunk.dynamic_imports) {
+      if let Some(chunk) = chunk_id_map.get_mut(&child) {
+        chunk.parents.push(id);
+      }
+    }
+  }
+
+  let mut chunk_mapping: HashMap<Location, Vec<Chunk>> = HashMap::new();
+  let mut module_sizes: HashMap<Location, usize> = HashMap::new();
+  for (id, output_chunk) in output_chunks.iter().enumerate() {
+    // modules that were rendered down to nothing have been tree shaken
+    for (module_id, size) in output_chunk.modules.iter().filter(|(_, size)| *size > 0) {
+      let location = match module_location(resolve, module_id) {
+        Some(location) => location,
+        None => {
+          tracing::debug!("Skipping module {:?}", module_id);
+          continue;
+        }
+      };
+      // a module can be split between chunks
+      *module_sizes.entry(location.clone()).or_default() += size;
+      chunk_mapping
+        .entry(location)
+        .or_default()
+        .push(chunk_id_map[&id].clone());
+    }
+  }
+
+  WebpackReport {
+    chunk_mapping,
+    chunk_id_map,
+    module_sizes,
+    module_stat_sizes: HashMap::new(),
+  }
+}
+
+// Module ids are absolute paths or relative to the project root. Virtual modules start with a
+// null byte and queries like `?vue&type=style` are not part of the file.
+fn module_location(resolve: &Resolver, module_id: &str) -> Option<Location> {
+  if module_id.starts_with('\0') {
+    return None;
+  }
+  let path = Path::new(module_id.split('?').next()?);
+
+  if path.is_absolute() {
+    if let Ok(location) = Location::new(path) {
+      return Some(location);
+    }
+  }
+  let relative_path = path.strip_prefix("/").unwrap_or(path);
+  Location::new(resolve.resolve_root.as_ref().join(relative_path)).ok()
 }
 
 pub mod v4 {
@@ -54,6 +307,8 @@ pub mod v4 {
     id: usize,
     size: usize,
     names: Vec<String>,
+    #[serde(default)]
+    files: Vec<String>,
   }
 
   #[derive(Debug, Deserialize)]
@@ -62,6 +317,7 @@ pub mod v4 {
     id: Option<usize>,
     name: String,
     chunks: Vec<usize>,
+    size: Option<usize>,
     // reasons: Vec<Reason>,
     modules: Option<Vec<Module>>,
   }
@@ -80,16 +336,27 @@ pub mod v4 {
   ) -> Result<WebpackReport, CoreError> {
     WebpackReportRaw::parse_from_reader(resolve, value)
   }
+
+  #[tracing::instrument(skip(resolve, value))]
+  pub fn create_report_from_value(
+    resolve: &Resolver,
+    value: serde_json::Value,
+  ) -> Result<WebpackReport, CoreError> {
+    WebpackReportRaw::parse(resolve, serde_json::from_value(value)?)
+  }
+
   impl WebpackReportRaw {
     pub fn parse_from_reader(
       resolve: &Resolver,
       value: impl Read,
     ) -> Result<WebpackReport, CoreError> {
+      Self::parse(resolve, serde_json::from_reader(value)?)
+    }
+
+    fn parse(resolve: &Resolver, raw_reports: Self) -> Result<WebpackReport, CoreError> {
       // Replace all of the loader paths
       let path_normalisation_regex = Regex::new(".+!").unwrap();
       let webpack_reports: Vec<Self> = {
-        let raw_reports: Self = serde_json::from_reader(value)?;
-
         if let Some(children) = raw_reports.children {
           children
         } else {
@@ -99,6 +366,7 @@ pub mod v4 {
 
       let mut output_map = HashMap::new();
       let mut chunk_map = HashMap::new();
+      let mut module_stat_sizes = HashMap::new();
       for webpack_report in webpack_reports {
         assert!(
           webpack_report.chunks.is_some(),
@@ -123,6 +391,9 @@ pub mod v4 {
               children: chunk.children,
               name: chunk.names.join(", "),
               parsed_size: chunk.size,
+              files: chunk.files,
+              gzip_size: None,
+              brotli_size: None,
             },
           );
         }
@@ -154,6 +425,9 @@ pub mod v4 {
               .join(&*path_normalisation_regex.replace_all(&module.name, ""));
 
             let location = Location::new(path)?;
+            if let Some(size) = module.size {
+              module_stat_sizes.insert(location.clone(), size);
+            }
             let mut chunks: Vec<WebpackChunk> = module
               .chunks
               .iter()
@@ -172,31 +446,478 @@ pub mod v4 {
       Ok(WebpackReport {
         chunk_id_map: chunk_map,
         chunk_mapping: output_map,
+        module_sizes: HashMap::new(),
+        module_stat_sizes,
+      })
+    }
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn it_can_parse_a_report() {
+      let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript");
+      let report = r#"{
+        "chunks": [
+          { "id": 0, "entry": true, "initial": true, "names": ["main"], "parents": [], "siblings": [], "children": [1], "size": 1024, "files": ["main.js"] },
+          { "id": 1, "entry": false, "initial": false, "names": [], "parents": [0], "siblings": [], "children": [], "size": 512 }
+        ],
+        "modules": [
+          {
+            "name": "./c.js + 1 modules",
+            "chunks": [0],
+            "modules": [
+              { "name": "./c.js", "chunks": [], "size": 10 },
+              { "name": "./node_modules/babel-loader/lib!./module/a.js", "chunks": [], "size": 20 }
+            ]
+          },
+          { "id": 3, "name": "./module/b.js", "chunks": [1], "size": 42 },
+          { "id": 4, "name": "./module/b.js (ignored)", "chunks": [1], "size": 1 }
+        ]
+      }"#;
+      let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+
+      let report = WebpackReportRaw::parse_from_reader(&resolver, report.as_bytes()).unwrap();
+
+      assert_eq!(report.chunk_id_map[&0].name, "main");
+      assert_eq!(report.chunk_id_map[&0].files, vec!["main.js".to_string()]);
+      assert_eq!(report.chunk_id_map[&1].parents, vec![0]);
+      let chunks_of = |path: &str| -> Vec<usize> {
+        report.chunk_mapping[&Location::new(root.join(path)).unwrap()]
+          .iter()
+          .map(|chunk| chunk.id)
+          .collect()
+      };
+      assert_eq!(chunks_of("c.js"), vec![0]);
+      assert_eq!(chunks_of("module/a.js"), vec![0]);
+      assert_eq!(chunks_of("module/b.js"), vec![1]);
+      assert_eq!(
+        report.module_stat_sizes[&Location::new(root.join("module/a.js")).unwrap()],
+        20
+      );
+    }
+  }
+}
+
+pub mod v5 {
+  use super::Chunk as WebpackChunk;
+  use crate::error::CoreError;
+  use crate::module::Location;
+  use crate::resolve::Resolver;
+  use crate::webpack_report::WebpackReport;
+  use serde::Deserialize;
+  use std::collections::HashMap;
+  use std::io::Read;
+
+  #[derive(Debug, Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct WebpackReportRaw {
+    #[serde(default)]
+    modules: Vec<Module>,
+    #[serde(default)]
+    chunks: Vec<Chunk>,
+    #[serde(default)]
+    named_chunk_groups: HashMap<String, ChunkGroup>,
+    #[serde(default)]
+    entrypoints: HashMap<String, ChunkGroup>,
+
+    #[serde(default)]
+    children: Vec<WebpackReportRaw>,
+  }
+
+  // Chunk ids are numbers in production and names like "src_pages_home_js" in development
+  #[derive(Debug, Deserialize, Clone, PartialEq, Eq, Hash)]
+  #[serde(untagged)]
+  enum ChunkId {
+    Number(usize),
+    Name(String),
+  }
+
+  #[derive(Debug, Deserialize, Clone)]
+  #[serde(rename_all = "camelCase")]
+  struct Chunk {
+    id: ChunkId,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    initial: bool,
+    // relations are only there with `chunkRelations`
+    #[serde(default)]
+    parents: Vec<ChunkId>,
+    #[serde(default)]
+    siblings: Vec<ChunkId>,
+    #[serde(default)]
+    children: Vec<ChunkId>,
+    #[serde(default)]
+    size: f64,
+    #[serde(default)]
+    files: Vec<String>,
+  }
+
+  #[derive(Debug, Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct ChunkGroup {
+    #[serde(default)]
+    chunks: Vec<ChunkId>,
+  }
+
+  #[derive(Debug, Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct Module {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    // the full request with loaders, `/a/babel-loader/lib/index.js??ruleSet[1]!/a/src/b.js`
+    identifier: Option<String>,
+    // relative to the webpack context, `./src/b.js + 3 modules` for concatenated modules
+    name: Option<String>,
+    // the resource without loaders or a query
+    name_for_condition: Option<String>,
+    #[serde(default)]
+    chunks: Vec<ChunkId>,
+    size: Option<f64>,
+    // concatenated modules
+    modules: Option<Vec<Module>>,
+    // groups like "modules by path"
+    children: Option<Vec<Module>>,
+  }
+
+  impl Module {
+    fn is_ignored(&self) -> bool {
+      let identifier = self.identifier.as_deref().unwrap_or_default();
+      let name = self.name.as_deref().unwrap_or_default();
+
+      self.kind.as_deref() == Some("runtime")
+        || identifier.starts_with("webpack/runtime/")
+        || identifier.starts_with("external ")
+        || identifier.starts_with("ignored|")
+        || name.contains(" (ignored)")
+        || name.contains(" sync ")
+    }
+
+    fn path(&self) -> Option<&str> {
+      self.name_for_condition.as_deref().or_else(|| {
+        let resource = self.identifier.as_deref()?.rsplit('!').next()?;
+        resource.split('?').next()
+      })
+    }
+  }
+
+  #[tracing::instrument(skip(resolve, value))]
+  pub fn create_report_from_reader(
+    resolve: &Resolver,
+    value: impl Read,
+  ) -> Result<WebpackReport, CoreError> {
+    WebpackReportRaw::parse(resolve, serde_json::from_reader(value)?)
+  }
+
+  #[tracing::instrument(skip(resolve, value))]
+  pub fn create_report_from_value(
+    resolve: &Resolver,
+    value: serde_json::Value,
+  ) -> Result<WebpackReport, CoreError> {
+    WebpackReportRaw::parse(resolve, serde_json::from_value(value)?)
+  }
+
+  impl WebpackReportRaw {
+    fn parse(resolve: &Resolver, raw_report: Self) -> Result<WebpackReport, CoreError> {
+      let webpack_reports = if raw_report.children.is_empty() {
+        vec![raw_report]
+      } else {
+        raw_report.children
+      };
+
+      let chunk_ids = assign_chunk_ids(&webpack_reports);
+      let numeric_ids = |ids: &[ChunkId]| -> Vec<usize> {
+        ids
+          .iter()
+          .filter_map(|id| chunk_ids.get(id))
+          .copied()
+          .collect()
+      };
+
+      let mut output_map = HashMap::new();
+      let mut chunk_map = HashMap::new();
+      let mut module_stat_sizes = HashMap::new();
+      for webpack_report in webpack_reports {
+        // chunks without names of their own are named after the groups they are in
+        let mut group_names: HashMap<&ChunkId, Vec<&str>> = HashMap::new();
+        for (name, group) in webpack_report
+          .entrypoints
+          .iter()
+          .chain(webpack_report.named_chunk_groups.iter())
+        {
+          for id in group.chunks.iter() {
+            let names = group_names.entry(id).or_default();
+            if !names.contains(&name.as_str()) {
+              names.push(name);
+            }
+          }
+        }
+
+        for chunk in webpack_report.chunks.iter() {
+          let id = chunk_ids[&chunk.id];
+          let name = if chunk.names.is_empty() {
+            group_names
+              .get(&chunk.id)
+              .map(|names| names.join(", "))
+              .unwrap_or_else(|| match &chunk.id {
+                ChunkId::Name(name) => name.clone(),
+                ChunkId::Number(_) => String::new(),
+              })
+          } else {
+            chunk.names.join(", ")
+          };
+
+          tracing::info!("Adding chunk {}", id);
+          chunk_map.insert(
+            id,
+            WebpackChunk {
+              id,
+              name,
+              initial: chunk.initial,
+              parents: numeric_ids(&chunk.parents),
+              siblings: numeric_ids(&chunk.siblings),
+              children: numeric_ids(&chunk.children),
+              parsed_size: chunk.size as usize,
+              files: chunk.files.clone(),
+              gzip_size: None,
+              brotli_size: None,
+            },
+          );
+        }
+
+        let mut queue = webpack_report.modules;
+        while let Some(module) = queue.pop() {
+          if module.is_ignored() {
+            continue;
+          }
+          if let Some(mut child_modules) = module.children {
+            queue.append(&mut child_modules);
+            continue;
+          }
+          // this is a concatenated module, its modules are in the same chunks
+          if let Some(mut child_modules) = module.modules {
+            for child_module in &mut child_modules {
+              child_module.chunks = module.chunks.clone()
+            }
+            queue.append(&mut child_modules);
+            continue;
+          }
+
+          let path = match module.path() {
+            Some(path) => resolve.resolve_root.as_ref().join(path),
+            None => continue,
+          };
+          let location = match Location::new(&path) {
+            Ok(location) => location,
+            Err(e) => {
+              tracing::warn!("Skipping module {:?}: {}", &path, e);
+              continue;
+            }
+          };
+
+          if let Some(size) = module.size {
+            module_stat_sizes.insert(location.clone(), size as usize);
+          }
+          let mut chunks: Vec<WebpackChunk> = numeric_ids(&module.chunks)
+            .iter()
+            .filter_map(|id| chunk_map.get(id).cloned())
+            .collect();
+          output_map
+            .entry(location)
+            .or_insert_with(Vec::new)
+            .append(&mut chunks);
+        }
+      }
+
+      tracing::info!("Webpack report generation complete");
+
+      Ok(WebpackReport {
+        chunk_id_map: chunk_map,
+        chunk_mapping: output_map,
+        module_sizes: HashMap::new(),
+        module_stat_sizes,
+      })
+    }
+  }
+
+  // The rest of chungus uses numeric chunk ids. Named chunks get the ids after the largest
+  // numeric one, in the order they are in the stats.
+  fn assign_chunk_ids(reports: &[WebpackReportRaw]) -> HashMap<ChunkId, usize> {
+    let all_ids = || {
+      reports
+        .iter()
+        .flat_map(|report| report.chunks.iter().map(|c| &c.id))
+    };
+    let mut next_id = all_ids()
+      .filter_map(|id| match id {
+        ChunkId::Number(id) => Some(id + 1),
+        ChunkId::Name(_) => None,
       })
+      .max()
+      .unwrap_or(0);
+
+    let mut chunk_ids = HashMap::new();
+    for id in all_ids() {
+      if chunk_ids.contains_key(id) {
+        continue;
+      }
+      let numeric_id = match id {
+        ChunkId::Number(id) => *id,
+        ChunkId::Name(_) => {
+          next_id += 1;
+          next_id - 1
+        }
+      };
+      chunk_ids.insert(id.clone(), numeric_id);
     }
+    chunk_ids
   }
 
   #[cfg(test)]
   mod tests {
     use super::*;
-    use crate::resolve::Resolver;
     use std::path::Path;
 
+    fn sample_report() -> String {
+      r#"{
+        "version": "5.51.1",
+        "chunks": [
+          { "id": 179, "names": ["main"], "initial": true, "children": ["src_module_b_js"], "size": 1024 },
+          { "id": "src_module_b_js", "names": [], "initial": false, "parents": [179], "size": 512.5 }
+        ],
+        "namedChunkGroups": {
+          "main": { "chunks": [179] },
+          "lazy-b": { "chunks": ["src_module_b_js"] }
+        },
+        "modules": [
+          {
+            "type": "module",
+            "identifier": "/loaders/babel-loader/lib/index.js??ruleSet[1].rules[0]!ROOT/c.js",
+            "name": "./c.js + 1 modules",
+            "chunks": [179],
+            "modules": [
+              { "type": "module", "identifier": "ROOT/c.js", "name": "./c.js", "nameForCondition": "ROOT/c.js", "chunks": [] },
+              { "type": "module", "identifier": "ROOT/module/a.js", "name": "./module/a.js", "nameForCondition": "ROOT/module/a.js", "chunks": [] }
+            ]
+          },
+          {
+            "type": "modules by path",
+            "name": "./module/",
+            "children": [
+              { "type": "module", "identifier": "/loaders/x.js!ROOT/module/b.js?inline", "name": "./module/b.js", "chunks": ["src_module_b_js"], "size": 42 }
+            ]
+          },
+          { "type": "runtime", "identifier": "webpack/runtime/define property getters", "name": "webpack/runtime/define property getters", "chunks": [179] },
+          { "type": "module", "identifier": "external \"React\"", "name": "external \"React\"", "chunks": [179] }
+        ]
+      }"#
+      .to_string()
+    }
+
     #[test]
     fn it_can_parse_a_report() {
-      let report = include_str!("sample_javascript/compilation-stats.json");
-      let resolver = Resolver::new(
-        &Location::new(Path::new(
-          "/home/kevin_velasco/data_disk/Projects/mathspace/mathspace/local_modules",
-        ))
-        .unwrap(),
-        vec![],
+      let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript");
+      let report = sample_report().replace("ROOT", &root.to_string_lossy());
+      let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+
+      let report = super::super::create_report_from_reader(&resolver, report.as_bytes()).unwrap();
+
+      let main = &report.chunk_id_map[&179];
+      assert_eq!(main.name, "main");
+      assert_eq!(main.children, vec![180]);
+      let lazy = &report.chunk_id_map[&180];
+      assert_eq!(lazy.name, "lazy-b");
+      assert_eq!(lazy.parents, vec![179]);
+      assert_eq!(lazy.parsed_size, 512);
+
+      let chunks_of = |path: &str| -> Vec<usize> {
+        report.chunk_mapping[&Location::new(root.join(path)).unwrap()]
+          .iter()
+          .map(|chunk| chunk.id)
+          .collect()
+      };
+      assert_eq!(chunks_of("c.js"), vec![179]);
+      assert_eq!(chunks_of("module/a.js"), vec![179]);
+      assert_eq!(chunks_of("module/b.js"), vec![180]);
+      assert_eq!(
+        report.module_stat_sizes[&Location::new(root.join("module/b.js")).unwrap()],
+        42
       );
+      assert_eq!(report.chunk_mapping.len(), 3);
+    }
 
-      let report = WebpackReportRaw::parse_from_string(&resolver, &report).unwrap();
+    #[test]
+    fn it_detects_the_webpack_version() {
+      let version = |json: &str| super::super::major_version(&serde_json::from_str(json).unwrap());
+      assert_eq!(version(r#"{ "version": "5.51.1" }"#), Some(5));
+      assert_eq!(
+        version(r#"{ "children": [{ "version": "4.46.0" }] }"#),
+        Some(4)
+      );
+      assert_eq!(version(r#"{ "modules": [] }"#), None);
+    }
+
+    #[test]
+    fn it_reads_stats_without_a_version() {
+      let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript");
+      let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+      let read = |json: String| super::super::create_report_from_reader(&resolver, json.as_bytes());
+
+      // `stats.toJson({ all: false, modules: true, chunks: true })`
+      let report = sample_report()
+        .replace("ROOT", &root.to_string_lossy())
+        .replace(r#""version": "5.51.1","#, "");
+      let report = read(report).unwrap();
+      assert_eq!(report.chunk_id_map[&180].name, "lazy-b");
 
-      dbg!(report);
-      todo!()
+      let error = read(r#"{ "assets": [] }"#.to_string()).unwrap_err();
+      assert!(error.to_string().contains("version: true"), "{}", error);
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_adds_compressed_sizes_to_chunks() {
+    let output_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript");
+    let chunk = Chunk {
+      id: 0,
+      name: "main".into(),
+      initial: true,
+      parents: vec![],
+      siblings: vec![],
+      children: vec![],
+      parsed_size: 0,
+      files: vec!["module/a.js".into(), "module/a.js.map".into()],
+      gzip_size: None,
+      brotli_size: None,
+    };
+    let location = Location::new(output_directory.join("module/a.js")).unwrap();
+    let mut report = WebpackReport {
+      chunk_mapping: vec![(location.clone(), vec![chunk.clone()])]
+        .into_iter()
+        .collect(),
+      chunk_id_map: vec![(0, chunk)].into_iter().collect(),
+      module_sizes: Default::default(),
+      module_stat_sizes: Default::default(),
+    };
+
+    report.add_compressed_sizes(&output_directory);
+
+    let contents = std::fs::read(output_directory.join("module/a.js")).unwrap();
+    let expected = crate::compression::compressed_sizes(&contents);
+    assert_eq!(report.chunk_id_map[&0].gzip_size, Some(expected.gzip));
+    assert_eq!(report.chunk_id_map[&0].brotli_size, Some(expected.brotli));
+    assert_eq!(
+      report.chunk_mapping[&location][0].gzip_size,
+      Some(expected.gzip)
+    );
+  }
+}
@@ -0,0 +1,192 @@
+use super::{create_report_from_output_chunks, OutputChunk};
+use crate::error::CoreError;
+use crate::resolve::Resolver;
+use crate::webpack_report::WebpackReport;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+// The json written by esbuild's `--metafile`. Paths in it are relative to the directory esbuild
+// ran in, which is expected to be the resolve root.
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Metafile {
+  #[serde(default)]
+  outputs: HashMap<String, Output>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Output {
+  #[serde(default)]
+  bytes: usize,
+  #[serde(default)]
+  inputs: HashMap<String, OutputInput>,
+  #[serde(default)]
+  imports: Vec<OutputImport>,
+  // set for entry points, including the ones split out for dynamic imports
+  entry_point: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputInput {
+  #[serde(default)]
+  bytes_in_output: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputImport {
+  path: String,
+  kind: String,
+}
+
+/// Whether a json value looks like an esbuild metafile
+pub fn is_esbuild_metafile(value: &serde_json::Value) -> bool {
+  value["inputs"].is_object() && value["outputs"].is_object()
+}
+
+#[tracing::instrument(skip(resolve, value))]
+pub fn create_report_from_reader(
+  resolve: &Resolver,
+  value: impl Read,
+) -> Result<WebpackReport, CoreError> {
+  create_report_from_value(resolve, serde_json::from_reader(value)?)
+}
+
+#[tracing::instrument(skip(resolve, value))]
+pub fn create_report_from_value(
+  resolve: &Resolver,
+  value: serde_json::Value,
+) -> Result<WebpackReport, CoreError> {
+  let metafile: Metafile = serde_json::from_value(value)?;
+
+  // source maps are outputs too
+  let outputs: Vec<(String, Output)> = metafile
+    .outputs
+    .into_iter()
+    .filter(|(file_name, _)| !file_name.ends_with(".map"))
+    .collect();
+
+  // entry points that are only there because of a dynamic import aren't loaded up front
+  let dynamically_imported: HashSet<&str> = outputs
+    .iter()
+    .flat_map(|(_, output)| output.imports.iter())
+    .filter(|import| import.kind == "dynamic-import")
+    .map(|import| import.path.as_str())
+    .collect();
+  let is_entry: Vec<bool> = outputs
+    .iter()
+    .map(|(file_name, output)| {
+      output.entry_point.is_some() && !dynamically_imported.contains(file_name.as_str())
+    })
+    .collect();
+
+  let output_chunks = outputs
+    .into_iter()
+    .zip(is_entry)
+    .map(|((file_name, output), is_entry)| {
+      let (dynamic_imports, imports): (Vec<OutputImport>, Vec<OutputImport>) = output
+        .imports
+        .into_iter()
+        .partition(|import| import.kind == "dynamic-import");
+
+      OutputChunk {
+        file_name,
+        is_entry,
+        imports: imports.into_iter().map(|import| import.path).collect(),
+        dynamic_imports: dynamic_imports
+          .into_iter()
+          .map(|import| import.path)
+          .collect(),
+        modules: output
+          .inputs
+          .into_iter()
+          .map(|(path, input)| (path, input.bytes_in_output))
+          .collect(),
+        size: Some(output.bytes),
+      }
+    })
+    .collect();
+
+  tracing::info!("esbuild report generation complete");
+  Ok(create_report_from_output_chunks(resolve, output_chunks))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::module::Location;
+  use std::path::Path;
+
+  #[test]
+  fn it_reads_a_metafile() {
+    let metafile = r#"{
+      "inputs": {
+        "c.js": { "bytes": 120, "imports": [{ "path": "module/a.js", "kind": "import-statement" }] },
+        "module/a.js": { "bytes": 60, "imports": [{ "path": "module/b.js", "kind": "dynamic-import" }] },
+        "module/b.js": { "bytes": 40, "imports": [] }
+      },
+      "outputs": {
+        "out/c.js": {
+          "bytes": 300,
+          "inputs": { "c.js": { "bytesInOutput": 100 }, "module/a.js": { "bytesInOutput": 0 } },
+          "imports": [
+            { "path": "out/chunk-ABC.js", "kind": "import-statement" },
+            { "path": "out/b.js", "kind": "dynamic-import" },
+            { "path": "react", "kind": "require-call", "external": true }
+          ],
+          "exports": [],
+          "entryPoint": "c.js"
+        },
+        "out/c.js.map": { "bytes": 900, "inputs": {}, "imports": [], "exports": [] },
+        "out/b.js": {
+          "bytes": 50,
+          "inputs": { "module/b.js": { "bytesInOutput": 35 } },
+          "imports": [{ "path": "out/chunk-ABC.js", "kind": "import-statement" }],
+          "exports": ["default"],
+          "entryPoint": "module/b.js"
+        },
+        "out/chunk-ABC.js": {
+          "bytes": 70,
+          "inputs": { "module/a.js": { "bytesInOutput": 55 } },
+          "imports": [],
+          "exports": ["a"]
+        }
+      }
+    }"#;
+    let value: serde_json::Value = serde_json::from_str(metafile).unwrap();
+    assert!(is_esbuild_metafile(&value));
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript");
+    let resolver = Resolver::new(&Location::new(&root).unwrap(), vec![]);
+    let report = create_report_from_value(&resolver, value).unwrap();
+
+    // out/b.js, out/c.js, out/chunk-ABC.js
+    assert_eq!(report.chunk_id_map.len(), 3);
+    let main = &report.chunk_id_map[&1];
+    assert!(main.initial);
+    assert_eq!(main.parsed_size, 300);
+    assert_eq!(main.siblings, vec![2]);
+    assert_eq!(main.children, vec![0]);
+    assert!(!report.chunk_id_map[&0].initial);
+    assert!(report.chunk_id_map[&2].initial);
+
+    let chunks_of = |path: &str| -> Vec<usize> {
+      report.chunk_mapping[&Location::new(root.join(path)).unwrap()]
+        .iter()
+        .map(|chunk| chunk.id)
+        .collect()
+    };
+    assert_eq!(chunks_of("c.js"), vec![1]);
+    // it has no bytes in out/c.js, only in the shared chunk
+    assert_eq!(chunks_of("module/a.js"), vec![2]);
+    assert_eq!(chunks_of("module/b.js"), vec![0]);
+    assert_eq!(
+      report.module_sizes[&Location::new(root.join("module/a.js")).unwrap()],
+      55
+    );
+  }
+}
@@ -0,0 +1,316 @@
+use super::{create_report_from_output_chunks, OutputChunk};
+use crate::error::CoreError;
+use crate::resolve::Resolver;
+use crate::webpack_report::WebpackReport;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Read;
+
+// Rollup (and vite) don't have stats files. Two kinds of bundle metadata are read instead:
+// - the raw data written by rollup-plugin-visualizer with `template: "raw-data"`
+// - the `bundle` passed to a `generateBundle` hook, written out with JSON.stringify
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VisualizerData {
+  node_parts: HashMap<String, NodePart>,
+  node_metas: HashMap<String, NodeMeta>,
+}
+
+// A module as rendered into one bundle
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NodePart {
+  #[serde(default)]
+  rendered_length: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeMeta {
+  // relative to the project root, `/src/main.ts`
+  id: String,
+  // bundle file name to the uid of the node part
+  #[serde(default)]
+  module_parts: HashMap<String, String>,
+  #[serde(default)]
+  imported: Vec<ImportedNode>,
+  #[serde(default)]
+  is_entry: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportedNode {
+  uid: String,
+  #[serde(default)]
+  dynamic: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum BundleItem {
+  Chunk(RollupChunk),
+  Asset {},
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RollupChunk {
+  file_name: String,
+  #[serde(default)]
+  is_entry: bool,
+  #[serde(default)]
+  imports: Vec<String>,
+  #[serde(default)]
+  dynamic_imports: Vec<String>,
+  #[serde(default)]
+  modules: HashMap<String, RenderedModule>,
+  code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenderedModule {
+  #[serde(default)]
+  rendered_length: usize,
+}
+
+/// Whether a json value looks like visualizer raw data or a rollup output bundle
+pub fn is_rollup_metadata(value: &serde_json::Value) -> bool {
+  if value.get("nodeMetas").is_some() && value.get("nodeParts").is_some() {
+    return true;
+  }
+
+  value
+    .as_object()
+    .filter(|bundle| !bundle.is_empty())
+    .map(|bundle| {
+      bundle.values().all(|item| {
+        matches!(item["type"].as_str(), Some("chunk") | Some("asset"))
+          && item["fileName"].is_string()
+      })
+    })
+    .unwrap_or(false)
+}
+
+#[tracing::instrument(skip(resolve, value))]
+pub fn create_report_from_reader(
+  resolve: &Resolver,
+  value: impl Read,
+) -> Result<WebpackReport, CoreError> {
+  create_report_from_value(resolve, serde_json::from_reader(value)?)
+}
+
+#[tracing::instrument(skip(resolve, value))]
+pub fn create_report_from_value(
+  resolve: &Resolver,
+  value: serde_json::Value,
+) -> Result<WebpackReport, CoreError> {
+  let output_chunks = if value.get("nodeMetas").is_some() {
+    output_chunks_from_visualizer(serde_json::from_value(value)?)
+  } else {
+    let bundle: HashMap<String, BundleItem> = serde_json::from_value(value)?;
+    bundle
+      .into_values()
+      .filter_map(|item| match item {
+        BundleItem::Chunk(chunk) => Some(chunk),
+        BundleItem::Asset {} => None,
+      })
+      .map(|chunk| OutputChunk {
+        size: chunk.code.as_ref().map(|code| code.len()),
+        file_name: chunk.file_name,
+        is_entry: chunk.is_entry,
+        imports: chunk.imports,
+        dynamic_imports: chunk.dynamic_imports,
+        modules: chunk
+          .modules
+          .into_iter()
+          .map(|(id, module)| (id, module.rendered_length))
+          .collect(),
+      })
+      .collect()
+  };
+
+  tracing::info!("Rollup report generation complete");
+  Ok(create_report_from_output_chunks(resolve, output_chunks))
+}
+
+// The visualizer data is per module, the chunks and how they import each other are rebuilt from
+// the bundles every module was rendered into
+fn output_chunks_from_visualizer(data: VisualizerData) -> Vec<OutputChunk> {
+  #[derive(Default)]
+  struct Bundle {
+    is_entry: bool,
+    imports: BTreeSet<String>,
+    dynamic_imports: BTreeSet<String>,
+    modules: Vec<(String, usize)>,
+  }
+
+  let mut bundles: BTreeMap<&str, Bundle> = BTreeMap::new();
+  for meta in data.node_metas.values() {
+    for (bundle_name, part_uid) in meta.module_parts.iter() {
+      let bundle = bundles.entry(bundle_name).or_default();
+      let rendered_length = data
+        .node_parts
+        .get(part_uid)
+        .map(|part| part.rendered_length)
+        .unwrap_or_default();
+      bundle.is_entry |= meta.is_entry;
+      bundle.modules.push((meta.id.clone(), rendered_length));
+
+      for imported in meta.imported.iter() {
+        let imported_bundles = data
+          .node_metas
+          .get(&imported.uid)
+          .map(|meta| meta.module_parts.keys())
+          .into_iter()
+          .flatten()
+          .filter(|imported_bundle| *imported_bundle != bundle_name)
+          .cloned();
+        if imported.dynamic {
+          bundle.dynamic_imports.extend(imported_bundles);
+        } else {
+          bundle.imports.extend(imported_bundles);
+        }
+      }
+    }
+  }
+
+  bundles
+    .into_iter()
+    .map(|(file_name, bundle)| OutputChunk {
+      file_name: file_name.to_string(),
+      is_entry: bundle.is_entry,
+      imports: bundle.imports.into_iter().collect(),
+      dynamic_imports: bundle.dynamic_imports.into_iter().collect(),
+      modules: bundle.modules,
+      size: None,
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::module::Location;
+  use std::path::{Path, PathBuf};
+
+  fn sample_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_javascript")
+  }
+
+  fn chunks_of(report: &WebpackReport, path: &str) -> Vec<String> {
+    let location = Location::new(sample_root().join(path)).unwrap();
+    report
+      .chunk_mapping
+      .get(&location)
+      .map(|chunks| chunks.iter().map(|chunk| chunk.name.clone()).collect())
+      .unwrap_or_default()
+  }
+
+  #[test]
+  fn it_reads_visualizer_raw_data() {
+    let data = r#"{
+      "version": 2,
+      "tree": { "name": "root", "children": [] },
+      "nodeParts": {
+        "p-1": { "renderedLength": 120, "gzipLength": 0, "brotliLength": 0, "metaUid": "m-1" },
+        "p-2": { "renderedLength": 80, "gzipLength": 0, "brotliLength": 0, "metaUid": "m-2" },
+        "p-3": { "renderedLength": 0, "gzipLength": 0, "brotliLength": 0, "metaUid": "m-3" },
+        "p-4": { "renderedLength": 10, "gzipLength": 0, "brotliLength": 0, "metaUid": "m-4" }
+      },
+      "nodeMetas": {
+        "m-1": {
+          "id": "/c.js",
+          "moduleParts": { "assets/index.js": "p-1" },
+          "imported": [{ "uid": "m-2", "dynamic": true }, { "uid": "m-3" }, { "uid": "m-4" }],
+          "importedBy": [],
+          "isEntry": true
+        },
+        "m-2": { "id": "/module/a.js", "moduleParts": { "assets/a.js": "p-2" }, "imported": [] },
+        "m-3": { "id": "/module/b.js", "moduleParts": { "assets/index.js": "p-3" }, "imported": [] },
+        "m-4": { "id": "\u0000vite/preload-helper", "moduleParts": { "assets/index.js": "p-4" } }
+      },
+      "env": { "rollup": "2.56.3" },
+      "options": { "gzip": false, "brotli": false, "sourcemap": false }
+    }"#;
+    let value: serde_json::Value = serde_json::from_str(data).unwrap();
+    assert!(is_rollup_metadata(&value));
+
+    let resolver = Resolver::new(&Location::new(sample_root()).unwrap(), vec![]);
+    let report = create_report_from_value(&resolver, value).unwrap();
+
+    let index = &report.chunk_id_map[&1];
+    assert_eq!(index.name, "index");
+    assert!(index.initial);
+    assert_eq!(index.children, vec![0]);
+    assert_eq!(index.parsed_size, 130);
+    let lazy = &report.chunk_id_map[&0];
+    assert!(!lazy.initial);
+    assert_eq!(lazy.parents, vec![1]);
+
+    assert_eq!(chunks_of(&report, "c.js"), vec!["index"]);
+    assert_eq!(chunks_of(&report, "module/a.js"), vec!["a"]);
+    // rendered to nothing, so it was tree shaken
+    assert!(chunks_of(&report, "module/b.js").is_empty());
+  }
+
+  #[test]
+  fn it_reads_a_rollup_bundle() {
+    let bundle = r#"{
+      "main.js": {
+        "type": "chunk",
+        "fileName": "main.js",
+        "isEntry": true,
+        "imports": ["shared.js"],
+        "dynamicImports": ["lazy.js"],
+        "modules": { "ROOT/c.js": { "renderedLength": 50, "originalLength": 70 } },
+        "code": "console.log('main')"
+      },
+      "shared.js": {
+        "type": "chunk",
+        "fileName": "shared.js",
+        "isEntry": false,
+        "imports": [],
+        "dynamicImports": [],
+        "modules": { "ROOT/module/a.js": { "renderedLength": 20 } }
+      },
+      "lazy.js": {
+        "type": "chunk",
+        "fileName": "lazy.js",
+        "isEntry": false,
+        "isDynamicEntry": true,
+        "imports": ["shared.js"],
+        "dynamicImports": [],
+        "modules": { "ROOT/module/b.js": { "renderedLength": 30 } }
+      },
+      "style.css": { "type": "asset", "fileName": "style.css", "source": "" }
+    }"#
+      .replace("ROOT", &sample_root().to_string_lossy());
+    let value: serde_json::Value = serde_json::from_str(&bundle).unwrap();
+    assert!(is_rollup_metadata(&value));
+
+    let resolver = Resolver::new(&Location::new(sample_root()).unwrap(), vec![]);
+    let report = create_report_from_value(&resolver, value).unwrap();
+
+    // lazy.js, main.js, shared.js
+    let main = &report.chunk_id_map[&1];
+    assert_eq!(main.siblings, vec![2]);
+    assert_eq!(main.children, vec![0]);
+    assert_eq!(main.parsed_size, "console.log('main')".len());
+    assert!(report.chunk_id_map[&2].initial);
+    assert!(!report.chunk_id_map[&0].initial);
+    assert_eq!(report.chunk_id_map[&0].parsed_size, 30);
+
+    assert_eq!(chunks_of(&report, "c.js"), vec!["main"]);
+    assert_eq!(chunks_of(&report, "module/a.js"), vec!["shared"]);
+    assert_eq!(chunks_of(&report, "module/b.js"), vec!["lazy"]);
+  }
+
+  #[test]
+  fn it_does_not_mistake_webpack_stats_for_a_bundle() {
+    let stats = serde_json::json!({ "version": "5.51.1", "chunks": [], "modules": [] });
+    assert!(!is_rollup_metadata(&stats));
+  }
+}
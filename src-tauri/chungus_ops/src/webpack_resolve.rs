@@ -0,0 +1,253 @@
+use crate::error::CoreError;
+use crate::resolve::{Alias, AliasTarget, Resolver};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+// A JSON description of webpack's `resolve` configuration. Webpack configs are javascript so
+// they have to be dumped first, something like:
+//   node -e 'console.log(JSON.stringify(require("./webpack.config.js").resolve))'
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct WebpackResolveRaw {
+  #[serde(default)]
+  alias: Option<AliasesRaw>,
+  #[serde(default)]
+  modules: Option<Vec<String>>,
+  #[serde(default)]
+  extensions: Option<Vec<String>>,
+  #[serde(default)]
+  condition_names: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AliasesRaw {
+  // `alias: { components: "...", "react$": "..." }`
+  Object(Map<String, Value>),
+  // webpack 5 also takes `alias: [{ name, alias, onlyModule }]`
+  List(Vec<AliasEntryRaw>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AliasEntryRaw {
+  name: String,
+  alias: Value,
+  #[serde(default)]
+  only_module: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WebpackResolveConfig {
+  pub aliases: Vec<Alias>,
+  /// `resolve.modules`, absolute directories or names looked up in every ancestor
+  pub modules: Option<Vec<String>>,
+  /// Without the leading `.`
+  pub extensions: Option<Vec<String>>,
+  pub condition_names: Option<Vec<String>>,
+}
+
+impl WebpackResolveConfig {
+  /// Reads either the `resolve` object itself or a whole config that has one. Relative alias
+  /// targets are resolved from `context`, usually the directory of the webpack config.
+  pub fn from_reader(reader: impl Read, context: impl AsRef<Path>) -> Result<Self, CoreError> {
+    let value: Value = serde_json::from_reader(reader)?;
+    let value = match value.get("resolve") {
+      Some(resolve) => resolve.clone(),
+      None => value,
+    };
+    let raw: WebpackResolveRaw = serde_json::from_value(value)?;
+    let context = context.as_ref();
+
+    let entries: Vec<(String, Value, bool)> = match raw.alias {
+      None => vec![],
+      Some(AliasesRaw::Object(map)) => map
+        .into_iter()
+        .map(|(name, alias)| match name.strip_suffix('$') {
+          Some(name) => (name.to_string(), alias, true),
+          None => (name, alias, false),
+        })
+        .collect(),
+      Some(AliasesRaw::List(list)) => list
+        .into_iter()
+        .map(|entry| (entry.name, entry.alias, entry.only_module))
+        .collect(),
+    };
+
+    let aliases = entries
+      .into_iter()
+      .map(|(name, alias, exact)| {
+        let targets = match alias {
+          Value::Array(targets) => targets
+            .iter()
+            .map(|target| alias_target(context, &name, target))
+            .collect::<Result<_, _>>()?,
+          target => vec![alias_target(context, &name, &target)?],
+        };
+        Ok(Alias {
+          name,
+          exact,
+          targets,
+        })
+      })
+      .collect::<Result<_, CoreError>>()?;
+
+    Ok(Self {
+      aliases,
+      modules: raw.modules,
+      extensions: raw.extensions.map(|extensions| {
+        extensions
+          .into_iter()
+          .map(|extension| extension.trim_start_matches('.').to_string())
+          .filter(|extension| !extension.is_empty())
+          .collect()
+      }),
+      condition_names: raw.condition_names,
+    })
+  }
+
+  /// Replaces the parts of the resolver that the config sets. Absolute `modules` become included
+  /// directories, anything else is searched for like node_modules.
+  pub fn apply(&self, resolver: &mut Resolver) {
+    resolver.aliases = self.aliases.clone();
+
+    if let Some(modules) = &self.modules {
+      let (directories, names): (Vec<&String>, Vec<&String>) = modules
+        .iter()
+        .partition(|module| Path::new(module).is_absolute());
+      resolver
+        .included_directories
+        .extend(directories.into_iter().map(PathBuf::from));
+      resolver.module_directories = names.into_iter().cloned().collect();
+    }
+
+    if let Some(extensions) = &self.extensions {
+      resolver.extensions = extensions.iter().cloned().collect();
+    }
+
+    if let Some(condition_names) = &self.condition_names {
+      resolver.conditions = condition_names.clone();
+    }
+  }
+}
+
+fn alias_target(context: &Path, name: &str, target: &Value) -> Result<AliasTarget, CoreError> {
+  match target {
+    Value::Bool(false) => Ok(AliasTarget::Ignored),
+    Value::String(target) if target.starts_with('.') => Ok(AliasTarget::Path(
+      context.join(target.strip_prefix("./").unwrap_or(target)),
+    )),
+    Value::String(target) if Path::new(target).is_absolute() => {
+      Ok(AliasTarget::Path(PathBuf::from(target)))
+    }
+    Value::String(target) => Ok(AliasTarget::Module(target.clone())),
+    _ => Err(CoreError::custom(&format!(
+      "Alias {:?} should be a path, a list of paths or false, found {}",
+      name, target
+    ))),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::module::{Asset, Dependency, Location};
+  use crate::parser::Import::Import;
+  use crate::parser::UnresolvedImport;
+
+  #[test]
+  fn it_reads_aliases_from_a_webpack_config() {
+    let config = r#"{
+      "resolve": {
+        "alias": {
+          "react$": "preact/compat",
+          "components": "./src/components",
+          "utils": ["/absolute/utils", "./fallback/utils"],
+          "fs": false
+        },
+        "modules": ["node_modules", "/project/src"],
+        "extensions": [".ts", ".js"]
+      }
+    }"#;
+    let config = WebpackResolveConfig::from_reader(config.as_bytes(), "/project").unwrap();
+
+    assert_eq!(
+      config.aliases,
+      vec![
+        Alias {
+          name: "react".into(),
+          exact: true,
+          targets: vec![AliasTarget::Module("preact/compat".into())],
+        },
+        Alias {
+          name: "components".into(),
+          exact: false,
+          targets: vec![AliasTarget::Path("/project/src/components".into())],
+        },
+        Alias {
+          name: "utils".into(),
+          exact: false,
+          targets: vec![
+            AliasTarget::Path("/absolute/utils".into()),
+            AliasTarget::Path("/project/fallback/utils".into()),
+          ],
+        },
+        Alias {
+          name: "fs".into(),
+          exact: false,
+          targets: vec![AliasTarget::Ignored],
+        },
+      ]
+    );
+    assert_eq!(config.extensions, Some(vec!["ts".into(), "js".into()]));
+
+    let list = r#"{ "alias": [{ "name": "lodash", "alias": "lodash-es", "onlyModule": true }] }"#;
+    let config = WebpackResolveConfig::from_reader(list.as_bytes(), "/project").unwrap();
+    assert_eq!(config.aliases[0].name, "lodash");
+    assert!(config.aliases[0].exact);
+
+    let invalid = r#"{ "alias": { "a": 1 } }"#;
+    assert!(WebpackResolveConfig::from_reader(invalid.as_bytes(), "/project").is_err());
+  }
+
+  #[test]
+  fn it_resolves_through_aliases() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sample_webpack_resolve");
+    let root = Location::new(&directory).unwrap();
+    let config = r#"{
+      "alias": {
+        "components": "./src/components",
+        "exact$": "./src/components/Button.js",
+        "fs": false
+      },
+      "modules": ["vendor", "node_modules"]
+    }"#;
+    let mut resolver = crate::resolve::Resolver::new(&root, vec![]);
+    WebpackResolveConfig::from_reader(config.as_bytes(), &directory)
+      .unwrap()
+      .apply(&mut resolver);
+
+    let module = resolver.resolve_normal_module(
+      &Location::new(directory.join("index.js")).unwrap(),
+      &[
+        UnresolvedImport::new_from_import(Import("components/Button".into())),
+        UnresolvedImport::new_from_import(Import("exact".into())),
+        UnresolvedImport::new_from_import(Import("exact/Button".into())),
+        UnresolvedImport::new_from_import(Import("fs".into())),
+        UnresolvedImport::new_from_import(Import("legacy".into())),
+      ],
+    );
+    let assets: Vec<&Asset> = module.dependencies.iter().map(Dependency::asset).collect();
+
+    let button = Location::new(directory.join("src/components/Button.js")).unwrap();
+    assert_eq!(assets[0], &Asset::Module(button.clone()));
+    assert_eq!(assets[1], &Asset::Module(button));
+    // `exact$` doesn't match subpaths
+    assert_eq!(assets[2], &Asset::Unresolved("exact/Button".into()));
+    assert_eq!(assets[3], &Asset::Ignored("fs".into()));
+    assert!(matches!(assets[4], Asset::NodePackage { .. }));
+  }
+}
@@ -1,7 +1,10 @@
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
-use chungus_ops::analysis::Analysis;
+use chungus_ops::analysis::{Analysis, Cycle, DuplicatePackage, ImportStep, RetainedSize};
+use chungus_ops::diagnostics::Diagnostics;
 use chungus_ops::module::Location;
+use chungus_ops::persistent_cache::{build_dependency_cache_persistent, PersistentCache};
 use chungus_ops::{DependencyCache, RwLock};
 
 use crate::application_state::State;
@@ -13,6 +16,36 @@ pub fn create_entrypoint_analysis(
   application_state: tauri::State<Arc<RwLock<State>>>,
   dependency_analysis: tauri::State<Arc<RwLock<DependencyAnalysis>>>,
   entrypoint: String,
+) -> Result<(), String> {
+  analyse_entrypoints(
+    window,
+    &application_state,
+    &dependency_analysis,
+    &[entrypoint],
+  )
+}
+
+/// One analysis over several entrypoints, every module is tagged with the ones that reach it
+#[tauri::command(async)]
+pub fn create_entrypoints_analysis(
+  window: tauri::Window,
+  application_state: tauri::State<Arc<RwLock<State>>>,
+  dependency_analysis: tauri::State<Arc<RwLock<DependencyAnalysis>>>,
+  entrypoints: Vec<String>,
+) -> Result<(), String> {
+  analyse_entrypoints(
+    window,
+    &application_state,
+    &dependency_analysis,
+    &entrypoints,
+  )
+}
+
+fn analyse_entrypoints(
+  window: tauri::Window,
+  application_state: &Arc<RwLock<State>>,
+  dependency_analysis: &Arc<RwLock<DependencyAnalysis>>,
+  entrypoints: &[String],
 ) -> Result<(), String> {
   tracing::info!("Creating entrypoint analysis");
   let loading = Loading::start(&window);
@@ -20,8 +53,11 @@ pub fn create_entrypoint_analysis(
   dependency_analysis.write().analysis = None;
   window.emit("entrypoint_analysis::sync", "");
 
-  let location = Location::new(&entrypoint)?;
-  let mut app_state = application_state.read();
+  let locations = entrypoints
+    .iter()
+    .map(Location::new)
+    .collect::<Result<Vec<_>, _>>()?;
+  let app_state = application_state.read();
   let resolver = &app_state.active_resolver;
 
   let mut dependency_cache = dependency_analysis.write();
@@ -29,13 +65,31 @@ pub fn create_entrypoint_analysis(
 
   loading.message("Building dependency cache");
 
-  chungus_ops::build_dependency_cache(resolver, &location, cache, &loading)?;
+  let mut persistent = PersistentCache::load(PersistentCache::default_path(resolver), resolver);
+  let mut diagnostics = Diagnostics::default();
+  for location in locations.iter() {
+    diagnostics.merge(build_dependency_cache_persistent(
+      resolver,
+      location,
+      cache,
+      &mut persistent,
+      &loading,
+    )?);
+  }
+  // the analysis doesn't need it, a later run just parses everything again
+  if let Err(e) = persistent.save() {
+    tracing::warn!("Could not save the dependency cache: {}", e);
+  }
 
   loading.message("Starting Analysis");
-  let mut analysis = Analysis::create_from_cache(&resolver, &cache, &location, &loading)?;
+  let mut analysis = Analysis::create_from_entrypoints(&resolver, &cache, &locations, &loading)?;
+  analysis.add_diagnostics(diagnostics);
   loading.message("Created Analysis");
   tracing::info!("Created entrypoint analysis");
 
+  loading.message("Compressing sources");
+  analysis.add_compressed_source_sizes();
+
   if let Some(ref webpack) = app_state.webpack_statistics {
     loading.message("Augmenting with webpack");
     analysis.augment_with_webpack_report(&webpack, 0);
@@ -68,8 +122,86 @@ pub fn get_entrypoint_analysis(
   out
 }
 
+/// The shortest import chains from the entrypoint to a module, for "why is this included"
+#[tauri::command(async)]
+pub fn get_import_chains(
+  dependency_analysis: tauri::State<Arc<RwLock<DependencyAnalysis>>>,
+  location: String,
+  limit: Option<usize>,
+) -> Result<Vec<Vec<ImportStep>>, String> {
+  let location = Location::new(&location)?;
+  let read_guard = dependency_analysis.read();
+  let analysis = read_guard
+    .analysis
+    .as_ref()
+    .ok_or_else(|| "There is no entrypoint analysis".to_string())?;
+
+  Ok(analysis.paths_to(&location, limit.unwrap_or(1))?)
+}
+
+/// The modules that retain the most bytes, everything only they pull in included
+#[tauri::command(async)]
+pub fn get_largest_retained(
+  dependency_analysis: tauri::State<Arc<RwLock<DependencyAnalysis>>>,
+  limit: Option<usize>,
+) -> Result<Vec<RetainedSize>, String> {
+  let read_guard = dependency_analysis.read();
+  let analysis = read_guard
+    .analysis
+    .as_ref()
+    .ok_or_else(|| "There is no entrypoint analysis".to_string())?;
+
+  Ok(analysis.largest_retained(limit.unwrap_or(10)))
+}
+
+/// Every import cycle in the entrypoint analysis
+#[tauri::command(async)]
+pub fn get_import_cycles(
+  dependency_analysis: tauri::State<Arc<RwLock<DependencyAnalysis>>>,
+) -> Result<Vec<Cycle>, String> {
+  let read_guard = dependency_analysis.read();
+  let analysis = read_guard
+    .analysis
+    .as_ref()
+    .ok_or_else(|| "There is no entrypoint analysis".to_string())?;
+
+  Ok(analysis.cycles().to_vec())
+}
+
+/// Packages that are installed more than once in the entrypoint analysis
+#[tauri::command(async)]
+pub fn get_duplicate_packages(
+  application_state: tauri::State<Arc<RwLock<State>>>,
+  dependency_analysis: tauri::State<Arc<RwLock<DependencyAnalysis>>>,
+) -> Result<Vec<DuplicatePackage>, String> {
+  let app_state = application_state.read();
+  let read_guard = dependency_analysis.read();
+  let analysis = read_guard
+    .analysis
+    .as_ref()
+    .ok_or_else(|| "There is no entrypoint analysis".to_string())?;
+
+  Ok(analysis.duplicate_packages(&app_state.active_resolver)?)
+}
+
+/// Imports in the entrypoint analysis that didn't resolve and files that couldn't be loaded
+#[tauri::command(async)]
+pub fn get_diagnostics(
+  dependency_analysis: tauri::State<Arc<RwLock<DependencyAnalysis>>>,
+) -> Result<Diagnostics, String> {
+  let read_guard = dependency_analysis.read();
+  let analysis = read_guard
+    .analysis
+    .as_ref()
+    .ok_or_else(|| "There is no entrypoint analysis".to_string())?;
+
+  Ok(analysis.diagnostics().clone())
+}
+
 #[derive(Default)]
 pub struct DependencyAnalysis {
   pub cache: DependencyCache,
   pub analysis: Option<Analysis>,
+  // set to stop the thread that is watching for file changes
+  pub watching: Option<Arc<AtomicBool>>,
 }
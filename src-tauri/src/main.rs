@@ -18,11 +18,13 @@ mod anaylsis;
 mod application_state;
 mod file_tree;
 mod loading;
+mod watch;
 mod webpack;
 
 use anaylsis::*;
 use application_state::*;
 use file_tree::*;
+use watch::*;
 use webpack::*;
 
 fn main() {
@@ -38,7 +40,15 @@ fn main() {
       create_root_tree,
       get_application_state,
       create_entrypoint_analysis,
+      create_entrypoints_analysis,
       get_entrypoint_analysis,
+      get_import_chains,
+      get_largest_retained,
+      get_import_cycles,
+      get_duplicate_packages,
+      get_diagnostics,
+      watch_entrypoint_analysis,
+      stop_watching_entrypoint_analysis,
       create_webpack_statstics,
     ])
     .run(tauri::generate_context!())
@@ -0,0 +1,112 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chungus_ops::analysis::AnalysisDelta;
+use chungus_ops::diagnostics::Diagnostics;
+use chungus_ops::error::CoreError;
+use chungus_ops::logging::ClientSideLogger;
+use chungus_ops::watch::{update_dependency_cache, DependencyWatcher};
+use chungus_ops::RwLock;
+
+use crate::anaylsis::DependencyAnalysis;
+use crate::application_state::State;
+
+// Updates happen in the background, they don't take over the window with the loading overlay
+struct TracingLogger;
+
+impl ClientSideLogger for TracingLogger {
+  fn message(&self, message: impl AsRef<str>) {
+    tracing::debug!("{}", message.as_ref());
+  }
+}
+
+/// Keeps the entrypoint analysis up to date while files are edited. Every change is parsed again
+/// and patched into the analysis, then `entrypoint_analysis::sync` is emitted with what changed.
+#[tauri::command(async)]
+pub fn watch_entrypoint_analysis(
+  window: tauri::Window,
+  application_state: tauri::State<Arc<RwLock<State>>>,
+  dependency_analysis: tauri::State<Arc<RwLock<DependencyAnalysis>>>,
+) -> Result<(), String> {
+  let stopped = Arc::new(AtomicBool::new(false));
+  if let Some(previous) = dependency_analysis
+    .write()
+    .watching
+    .replace(stopped.clone())
+  {
+    previous.store(true, Ordering::Relaxed);
+  }
+
+  let mut watcher = DependencyWatcher::new(Duration::from_millis(200))?;
+  let application_state = application_state.inner().clone();
+  let dependency_analysis = dependency_analysis.inner().clone();
+  std::thread::spawn(move || {
+    while !stopped.load(Ordering::Relaxed) {
+      {
+        let app_state = application_state.read();
+        let analysis = dependency_analysis.read();
+        if let Err(e) = watcher.watch_cache(&app_state.active_resolver, &analysis.cache) {
+          tracing::warn!("Could not watch the dependency cache: {}", e);
+          break;
+        }
+      }
+
+      // wakes up now and then to see if it was stopped
+      let changed = match watcher.changes(Duration::from_millis(500)) {
+        Ok(changed) => changed,
+        Err(_) => break,
+      };
+      if changed.is_empty() {
+        continue;
+      }
+
+      match update_analysis(&application_state, &dependency_analysis, &changed) {
+        Ok(Some(delta)) => {
+          if let Err(e) = window.emit("entrypoint_analysis::sync", delta) {
+            tracing::warn!("Could not send the analysis changes: {}", e);
+          }
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Could not update the entrypoint analysis: {}", e),
+      }
+    }
+    tracing::info!("Stopped watching the entrypoint analysis");
+  });
+
+  Ok(())
+}
+
+#[tauri::command(async)]
+pub fn stop_watching_entrypoint_analysis(
+  dependency_analysis: tauri::State<Arc<RwLock<DependencyAnalysis>>>,
+) {
+  if let Some(watching) = dependency_analysis.write().watching.take() {
+    watching.store(true, Ordering::Relaxed);
+  }
+}
+
+fn update_analysis(
+  application_state: &Arc<RwLock<State>>,
+  dependency_analysis: &Arc<RwLock<DependencyAnalysis>>,
+  changed: &[PathBuf],
+) -> Result<Option<AnalysisDelta>, CoreError> {
+  let app_state = application_state.read();
+  let resolver = &app_state.active_resolver;
+  let mut dependency_analysis = dependency_analysis.write();
+  let DependencyAnalysis {
+    cache, analysis, ..
+  } = &mut *dependency_analysis;
+
+  let mut diagnostics = Diagnostics::default();
+  let changed = update_dependency_cache(resolver, cache, changed, &mut diagnostics, &TracingLogger);
+  match analysis {
+    Some(analysis) if !changed.is_empty() => {
+      let delta = analysis.update(resolver, cache, &changed, &TracingLogger)?;
+      analysis.add_diagnostics(diagnostics);
+      Ok(Some(delta).filter(|delta| !delta.is_empty()))
+    }
+    _ => Ok(None),
+  }
+}
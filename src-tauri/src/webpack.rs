@@ -57,7 +57,7 @@ pub fn create_webpack_statstics(
 
     let reader = BufReader::new(file);
     loading.message("Creating report");
-    let report = chungus_ops::webpack_report::v4::create_report_from_reader(resolver, reader)?;
+    let report = chungus_ops::webpack_report::create_report_from_reader(resolver, reader)?;
     drop(app_state);
     {
       let mut dependency_cache = dependency_analysis.write();
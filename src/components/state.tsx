@@ -92,6 +92,19 @@ export function useAnalyseEntrypoint() {
   };
 }
 
+export function useAnalyseEntrypoints() {
+  return async (entrypoints: string[]) => {
+    try {
+      await invoke("create_entrypoints_analysis", { entrypoints });
+    } catch (e) {
+      AppToaster.show({
+        message: "Unable to analyze entrypoints",
+        intent: Intent.DANGER,
+      });
+    }
+  };
+}
+
 export type Analysis = {
   node_map: Record<string, AnalysisNode>;
   file_tree: RootTree;
@@ -99,6 +112,18 @@ export type Analysis = {
   all_nodes: AnalysisNode[];
   entrypoint: AnalysisNode;
   chunks: {};
+  cycles: Cycle[];
+  // indices into all_nodes, the first one is the entrypoint
+  entrypoints: number[];
+};
+
+export type Sizes = {
+  source: number;
+  parsed: number | null;
+  // webpack's size before minification
+  stat: number | null;
+  gzip: number | null;
+  brotli: number | null;
 };
 
 export type AnalysisNode = {
@@ -108,6 +133,12 @@ export type AnalysisNode = {
   chunk: number;
   incoming: AnalysisNode[];
   outgoing: AnalysisNode[];
+  self_size: Sizes;
+  inclusive_size: Sizes;
+  // index into the analysis' cycles
+  cycle: number | null;
+  // indices into all_nodes of the entrypoints that reach this module
+  entrypoints: number[];
 };
 
 type RawAnalysis = {
@@ -196,3 +227,102 @@ export function useEntrypointAnalysis(): [
 
   return [output, { loading }];
 }
+
+export type ImportStep = {
+  importer: string;
+  imported: string;
+  kind: "import" | "require" | "async_import";
+};
+
+// The shortest import chains from the entrypoint to a module
+export function getImportChains(
+  location: string,
+  limit: number = 1
+): Promise<ImportStep[][]> {
+  return invoke("get_import_chains", { location, limit });
+}
+
+export type RetainedSize = {
+  location: string;
+  immediate_dominator: string | null;
+  retained_size: Sizes;
+  retained_modules: number;
+};
+
+// The modules that would take the most bytes with them if they were removed
+export function getLargestRetained(limit: number = 10): Promise<RetainedSize[]> {
+  return invoke("get_largest_retained", { limit });
+}
+
+export type Cycle = {
+  members: string[];
+  closing_edges: ImportStep[];
+};
+
+export function getImportCycles(): Promise<Cycle[]> {
+  return invoke("get_import_cycles");
+}
+
+export type PackageCopy = {
+  version: string | null;
+  directory: string;
+  modules: string[];
+  imported_by: string[];
+  size: Sizes;
+};
+
+export type DuplicatePackage = {
+  name: string;
+  copies: PackageCopy[];
+  wasted_size: Sizes;
+};
+
+export function getDuplicatePackages(): Promise<DuplicatePackage[]> {
+  return invoke("get_duplicate_packages");
+}
+
+export type ImportEdge = {
+  importer: string;
+  imported: string;
+};
+
+// Sent with entrypoint_analysis::sync while the analysis is being watched
+export type AnalysisDelta = {
+  changed_modules: string[];
+  added_modules: string[];
+  removed_modules: string[];
+  added_imports: ImportEdge[];
+  removed_imports: ImportEdge[];
+};
+
+export function watchEntrypointAnalysis(): Promise<void> {
+  return invoke("watch_entrypoint_analysis");
+}
+
+export function stopWatchingEntrypointAnalysis(): Promise<void> {
+  return invoke("stop_watching_entrypoint_analysis");
+}
+
+export type UnresolvedImportDiagnostic = {
+  importer: string;
+  specifier: string;
+  kind: "import" | "require" | "async_import";
+  line: number | null;
+  column: number | null;
+};
+
+export type FileError = {
+  location: string;
+  importer: string | null;
+  kind: "unreadable" | "malformed_package_json";
+  message: string;
+};
+
+export type Diagnostics = {
+  unresolved_imports: UnresolvedImportDiagnostic[];
+  file_errors: FileError[];
+};
+
+export function getDiagnostics(): Promise<Diagnostics> {
+  return invoke("get_diagnostics");
+}